@@ -41,4 +41,19 @@ impl Parser {
             Err(e) => Err(PyValueError::new_err(format!("{:?}", e))),
         }
     }
+
+    /// One result per visit, in input order: either the parsed visit or, for a visit that failed
+    /// to parse, the `ValueError` that `parse_visit` would have raised
+    fn parse_visits(&self, py: Python<'_>, visits: Vec<BTreeMap<String, String>>) -> Vec<PyObject> {
+        // Parsing happens in parallel on the Rust side, so let go of the GIL meanwhile
+        let parser = &self.parser;
+        let results = py.allow_threads(|| parser.parse_visits(visits));
+        results
+            .into_iter()
+            .map(|result| match result {
+                Ok(result) => PyWrap(result).into_py(py),
+                Err(e) => PyValueError::new_err(format!("{:?}", e)).into_py(py),
+            })
+            .collect()
+    }
 }
@@ -47,6 +47,12 @@ pywrap_enum!(DistanceOfMeasurement);
 pywrap_enum!(Correction);
 pywrap_enum!(PinHole);
 pywrap_enum!(VAFormat);
+pywrap_enum!(LowVisionConvention);
+pywrap_enum!(Optotype);
+pywrap_enum!(Qualifier);
+pywrap_enum!(FixationPreference);
+pywrap_enum!(EyePreference);
+pywrap_enum!(NotTakenReason);
 
 impl IntoPy<PyObject> for PyWrap<visualacuity::Visit> {
     fn into_py(self, py: Python<'_>) -> PyObject {
@@ -65,6 +71,20 @@ impl IntoPy<PyObject> for PyWrap<visualacuity::Fraction> {
     }
 }
 
+impl IntoPy<PyObject> for PyWrap<visualacuity::Fixation> {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        let fixation = self.0;
+        let args = (
+            PyWrap(fixation.preference),
+            fixation.central,
+            fixation.steady,
+            fixation.maintained,
+            fixation.eye_preference.map(PyWrap),
+        );
+        py_call(py, "Fixation", args).expect("boilerplate")
+    }
+}
+
 impl IntoPy<PyObject> for PyWrap<visualacuity::VisitNote> {
     fn into_py(self, py: Python<'_>) -> PyObject {
         py_call(
@@ -86,6 +106,21 @@ impl IntoPy<PyObject> for PyWrap<visualacuity::VisitNote> {
                     PyWrap(self.0.snellen_equivalent.map(PyWrap)).into_py(py),
                     PyWrap(self.0.log_mar_base).into_py(py),
                     PyWrap(self.0.log_mar_base_plus_letters).into_py(py),
+                    PyWrap(self.0.decimal_acuity).into_py(py),
+                    PyWrap(self.0.minimum_angle_of_resolution).into_py(py),
+                    PyWrap(self.0.visual_acuity_score).into_py(py),
+                    self.0.low_vision_convention.map(PyWrap).into_py(py),
+                    self.0.optotype.map(PyWrap).into_py(py),
+                    PyWrap(self.0.qualifier).into_py(py),
+                    self.0
+                        .observations
+                        .into_iter()
+                        .map(PyWrap)
+                        .collect::<Vec<_>>()
+                        .into_py(py),
+                    self.0.inherited_from.into_py(py),
+                    self.0.fixation.map(PyWrap).into_py(py),
+                    self.0.not_taken_reason.map(PyWrap).into_py(py),
                 ],
             ),
         )
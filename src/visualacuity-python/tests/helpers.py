@@ -16,6 +16,9 @@ def close_enough_visit(visit: Dict[str, VisitNote]):
             snellen_equivalent=try_snellen(visit_note.snellen_equivalent),
             log_mar_base=try_float(visit_note.log_mar_base),
             log_mar_base_plus_letters=try_float(visit_note.log_mar_base_plus_letters),
+            decimal_acuity=try_float(visit_note.decimal_acuity),
+            minimum_angle_of_resolution=try_float(visit_note.minimum_angle_of_resolution),
+            visual_acuity_score=try_float(visit_note.visual_acuity_score),
         )
     return result
 
@@ -1,7 +1,9 @@
 import unittest
+from unittest import mock
 
 from .helpers import close_enough_visit
 from visualacuity import *
+from visualacuity.cli import MapReduceLoader
 
 
 class TestVAInterface(unittest.TestCase):
@@ -22,6 +24,9 @@ class TestVAInterface(unittest.TestCase):
                     snellen_equivalent=(20.0, 30.0),
                     log_mar_base=0.17609125905568127,
                     log_mar_base_plus_letters=0.20107900637734125,
+                    decimal_acuity=0.6293916741966012,
+                    minimum_angle_of_resolution=1.5888357615732185,
+                    visual_acuity_score=89.94604968113293,
                 )}
             ),
             (
@@ -37,6 +42,9 @@ class TestVAInterface(unittest.TestCase):
                     snellen_equivalent=(20.0, 20.0),
                     log_mar_base=0.0,
                     log_mar_base_plus_letters=-0.041646245536099975,
+                    decimal_acuity=1.100642416298209,
+                    minimum_angle_of_resolution=0.9085602964160698,
+                    visual_acuity_score=102.082312276805,
                 )}
             ),
             (
@@ -61,6 +69,9 @@ class TestVAInterface(unittest.TestCase):
                         snellen_equivalent=(20, 20),
                         log_mar_base=0.0,
                         log_mar_base_plus_letters=0.03230333766935213,
+                        decimal_acuity=0.9283177667225558,
+                        minimum_angle_of_resolution=1.077217345015942,
+                        visual_acuity_score=98.38483311653239,
                     ),
                     "Both Eyes Distance SC": VisitNote(
                         data_quality=EXACT,
@@ -73,6 +84,9 @@ class TestVAInterface(unittest.TestCase):
                         snellen_equivalent=(20.0, 20.0),
                         log_mar_base=0.0,
                         log_mar_base_plus_letters=0.016151668834676065,
+                        decimal_acuity=0.9634924839989961,
+                        minimum_angle_of_resolution=1.0378908155562134,
+                        visual_acuity_score=99.1924165582662,
                     ),
                     "Both Eyes Near CC": VisitNote(
                         data_quality=EXACT,
@@ -84,6 +98,9 @@ class TestVAInterface(unittest.TestCase):
                         snellen_equivalent=(20.0, 25.0),
                         log_mar_base=0.09691001300805639,
                         log_mar_base_plus_letters=0.09691001300805639,
+                        decimal_acuity=0.8,
+                        minimum_angle_of_resolution=1.25,
+                        visual_acuity_score=95.15449934959717,
                     )
                 }
             ),
@@ -95,3 +112,69 @@ class TestVAInterface(unittest.TestCase):
             actual = close_enough_visit(parse_visit(input))
 
             self.assertEqual(actual, expected)
+
+    def test_parse_visit_low_vision_convention(self):
+        visit = parse_visit({"Left Eye": "CF 2ft", "Right Eye": "20/40"})
+        self.assertEqual(visit["Left Eye"].low_vision_convention, LowVisionConvention.SCHULZE_BONZEL)
+        self.assertEqual(visit["Right Eye"].low_vision_convention, None)
+
+    def test_parse_visit_optotype(self):
+        visit = parse_visit({"Left Eye": "HOTV 20/32", "Right Eye": "20/40"})
+        self.assertEqual(visit["Left Eye"].optotype, Optotype.HOTV)
+        self.assertEqual(visit["Right Eye"].optotype, None)
+
+    def test_parse_visit_qualifier(self):
+        visit = parse_visit({"Left Eye": "<20/400", "Right Eye": "20/40"})
+        self.assertEqual(visit["Left Eye"].qualifier, Qualifier.LESS_THAN)
+        self.assertEqual(visit["Right Eye"].qualifier, Qualifier.EXACT)
+
+    def test_parse_visit_observations(self):
+        visit = parse_visit({"Right Eye": "20/40 PH 20/25", "Left Eye": "20/30"})
+        self.assertEqual(visit["Right Eye"].data_quality, OBSERVATIONS)
+        self.assertEqual(visit["Right Eye"].extracted_value, "")
+        self.assertEqual(
+            [(o.pinhole, o.extracted_value) for o in visit["Right Eye"].observations],
+            [(PinHole.WITHOUT, "20/40"), (PinHole.WITH, "20/25")],
+        )
+        self.assertEqual(visit["Left Eye"].observations, [])
+
+    def test_parse_visit_inherited_from(self):
+        visit = parse_visit({"Right Eye": "20/40", "Right Eye PH": "NI"})
+        self.assertEqual(visit["Right Eye PH"].inherited_from, "Right Eye")
+        self.assertEqual(visit["Right Eye PH"].extracted_value, "20/40")
+        self.assertEqual(visit["Right Eye"].inherited_from, None)
+
+    def test_parse_visit_fixation(self):
+        visit = parse_visit({"Left Eye": "CSM prefers", "Right Eye": "20/40 CSUM"})
+        self.assertEqual(
+            visit["Left Eye"].fixation,
+            Fixation(FixationPreference.CSM, True, True, True, EyePreference.PREFERS),
+        )
+        self.assertEqual(visit["Right Eye"].extracted_value, "20/40")
+        self.assertEqual(visit["Right Eye"].fixation.preference, FixationPreference.CSUM)
+        self.assertEqual(visit["Right Eye"].fixation.maintained, False)
+
+    def test_parse_visit_not_taken_reason(self):
+        visit = parse_visit({"Left Eye": "refused", "Right Eye": "20/40"})
+        self.assertEqual(visit["Left Eye"].not_taken_reason, NotTakenReason.REFUSED)
+        self.assertEqual(visit["Right Eye"].not_taken_reason, None)
+
+    def test_parse_visits(self):
+        visits = [{"Left Eye": "20/40"}, {"Right Eye": "CF 2ft"}, {"Left Eye": "20/40 PH 20/25"}]
+        self.assertEqual(parse_visits(visits), [parse_visit(visit) for visit in visits])
+
+    def test_parse_batch_raises_failed_visit(self):
+        class Loader(MapReduceLoader):
+            def map(self, visit):
+                return visit
+
+            def reduce(self, accum, mapped):
+                return mapped
+
+        visit = parse_visit({"Left Eye": "20/40"})
+        error = ValueError("MultipleErrors")
+        with mock.patch("visualacuity.parse_visits", return_value=[visit, error, visit]):
+            batch = Loader().parse_batch([{}, {}, {}])
+            self.assertEqual(next(batch), visit)
+            with self.assertRaises(ValueError):
+                next(batch)
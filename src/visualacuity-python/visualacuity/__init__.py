@@ -9,9 +9,17 @@ from visualacuity._types import (
     VAFormat,
     PinHole,
     SnellenFraction,
+    LowVisionConvention,
+    Optotype,
+    Qualifier,
+    FixationPreference,
+    EyePreference,
+    Fixation,
+    NotTakenReason,
 )
 from visualacuity._parse import (
     parse_visit,
+    parse_visits,
 )
 
 # Alias some enums for convenience:
@@ -22,6 +30,8 @@ MULTIPLE = DataQuality.MULTIPLE
 CROSS_REFERENCE = DataQuality.CROSS_REFERENCE
 CONVERTIBLE_CONFIDENT = DataQuality.CONVERTIBLE_CONFIDENT
 CONVERTIBLE_FUZZY = DataQuality.CONVERTIBLE_FUZZY
+RANGE = DataQuality.RANGE
+OBSERVATIONS = DataQuality.OBSERVATIONS
 
 OS = Laterality.OS
 OD = Laterality.OD
@@ -1,4 +1,4 @@
-from typing import Dict
+from typing import Dict, List, Union
 
 import visualacuity
 
@@ -9,3 +9,6 @@ class Parser:
 
     def parse_visit(self, notes: Dict[str, str]) -> visualacuity.Visit:
         ...
+
+    def parse_visits(self, visits: List[Dict[str, str]]) -> List[Union[visualacuity.Visit, ValueError]]:
+        ...
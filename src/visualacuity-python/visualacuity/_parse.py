@@ -1,4 +1,4 @@
-from typing import Dict
+from typing import Dict, Iterable, List, Union
 
 from . import _lib, Visit
 
@@ -9,6 +9,15 @@ def parse_visit(notes: Dict[str, str]) -> Visit:
     return PARSER.parse_visit(notes)
 
 
+def parse_visits(visits: Iterable[Dict[str, str]]) -> List[Union[Visit, ValueError]]:
+    """
+    Parse a batch of visits in parallel, returning results in input order. A visit that fails to
+    parse is returned as the `ValueError` that `parse_visit` would raise, so the rest of the batch
+    is kept.
+    """
+    return PARSER.parse_visits(list(visits))
+
+
 def _try_get_attr(obj, attr, cast=None):
     try:
         value = getattr(obj, attr)
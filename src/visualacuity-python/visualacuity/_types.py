@@ -15,6 +15,8 @@ class DataQuality(_OrderedEnumMixIn, _FancyEnumMixIn, Enum):
     CROSS_REFERENCE = "CrossReference"
     CONVERTIBLE_CONFIDENT = "ConvertibleConfident"
     CONVERTIBLE_FUZZY = "ConvertibleFuzzy"
+    RANGE = "Range"
+    OBSERVATIONS = "Observations"
 
 
 class Laterality(_FancyEnumMixIn, Enum):
@@ -43,7 +45,16 @@ class VAFormat(_FancyEnumMixIn, Enum):
     JAEGER = "Jaeger"
     ETDRS = "ETDRS"
     TELLER = "Teller"
+    DECIMAL = "Decimal"
+    LOG_MAR = "LogMAR"
     NEAR_TOTAL_LOSS = "NearTotalLoss"
+    NEAR_CARD = "NearCard"
+    HOTV = "HOTV"
+    LEA = "Lea"
+    ALLEN = "Allen"
+    KAY = "Kay"
+    CARDIFF = "Cardiff"
+    KEELER = "Keeler"
     VISUAL_RESPONSE = "VisualResponse"
     PIN_HOLE = "PinHole"
     BINOCULAR = "Binocular"
@@ -57,6 +68,59 @@ class PinHole(_FancyEnumMixIn, Enum):
     WITHOUT = "Without"
 
 
+class LowVisionConvention(_FancyEnumMixIn, Enum):
+    SCHULZE_BONZEL = "SchulzeBonzel"
+    HOLLADAY = "Holladay"
+    LANGE = "Lange"
+
+
+class Optotype(_FancyEnumMixIn, Enum):
+    HOTV = "HOTV"
+    LEA = "Lea"
+    ALLEN = "Allen"
+    KAY = "Kay"
+    CARDIFF = "Cardiff"
+
+
+class Qualifier(_FancyEnumMixIn, Enum):
+    EXACT = "Exact"
+    LESS_THAN = "LessThan"
+    GREATER_THAN = "GreaterThan"
+
+
+class FixationPreference(_FancyEnumMixIn, Enum):
+    CSM = "CSM"
+    CUSM = "CUSM"
+    CSUM = "CSUM"
+    CUSUM = "CUSUM"
+    UCSM = "UCSM"
+    UCUSM = "UCUSM"
+    UCSUM = "UCSUM"
+    UCUSUM = "UCUSUM"
+    FIX_AND_FOLLOW = "FixAndFollow"
+    NO_FIX_AND_FOLLOW = "NoFixAndFollow"
+    FIX_NO_FOLLOW = "FixNoFollow"
+    ECCENTRIC = "Eccentric"
+
+
+class EyePreference(_FancyEnumMixIn, Enum):
+    PREFERS = "Prefers"
+    HOLDS = "Holds"
+
+
+class NotTakenReason(_FancyEnumMixIn, Enum):
+    NT = "NT"
+    UNABLE = "Unable"
+    REFUSED = "Refused"
+    SLEEPING = "Sleeping"
+    PROSTHESIS = "Prosthesis"
+    SEE_MR = "SeeMR"
+    POOR_COOPERATION = "PoorCooperation"
+    PATCHED = "Patched"
+    DILATED = "Dilated"
+    NO_TIME = "NoTime"
+
+
 class SnellenFraction(NamedTuple):
     distance: Number
     row: Number
@@ -71,6 +135,14 @@ class SnellenFraction(NamedTuple):
         return f"{self.distance}/{self.row}"
 
 
+class Fixation(NamedTuple):
+    preference: FixationPreference
+    central: Optional[bool] = None
+    steady: Optional[bool] = None
+    maintained: Optional[bool] = None
+    eye_preference: Optional[EyePreference] = None
+
+
 @dataclass(unsafe_hash=True)
 class VisitNote:
     text: str = ""
@@ -86,6 +158,16 @@ class VisitNote:
     snellen_equivalent: Optional[SnellenFraction] = None
     log_mar_base: Optional[float] = None
     log_mar_base_plus_letters: Optional[float] = None
+    decimal_acuity: Optional[float] = None
+    minimum_angle_of_resolution: Optional[float] = None
+    visual_acuity_score: Optional[float] = None
+    low_vision_convention: Optional[LowVisionConvention] = None
+    optotype: Optional[Optotype] = None
+    qualifier: Qualifier = Qualifier.EXACT
+    observations: List["VisitNote"] = field(default_factory=list)
+    inherited_from: Optional[str] = None
+    fixation: Optional[Fixation] = None
+    not_taken_reason: Optional[NotTakenReason] = None
 
     @classmethod
     def build(cls, *args, **kwargs):
@@ -100,6 +182,14 @@ class VisitNote:
             "correction": lambda value: Correction.get(value, Correction.UNKNOWN),
             "pinhole": lambda value: PinHole.get(value, PinHole.UNKNOWN),
             "va_format": lambda value: VAFormat.get(value, VAFormat.UNKNOWN),
+            "low_vision_convention": lambda value: LowVisionConvention.get(value),
+            "optotype": lambda value: Optotype.get(value),
+            "qualifier": lambda value: Qualifier.get(value, Qualifier.EXACT),
+            "observations": lambda value: [cls.build(*observation) for observation in value or []],
+            "fixation": lambda value: value and Fixation(
+                FixationPreference.get(value[0]), *value[1:4], EyePreference.get(value[4])
+            ),
+            "not_taken_reason": lambda value: NotTakenReason.get(value),
         }
         for field, cast in casts.items():
             if field in kwargs:
@@ -4,7 +4,8 @@ import json
 import logging
 import multiprocessing
 from functools import partial, lru_cache
-from typing import TypeVar, Generic, Dict
+from itertools import islice
+from typing import TypeVar, Generic, Dict, Iterator, List
 
 import visualacuity
 from visualacuity import Visit
@@ -23,9 +24,10 @@ TReduce = TypeVar('TReduce')
 
 class MapReduceLoader(Generic[TMap, TReduce], metaclass=abc.ABCMeta):
 
-    def __init__(self, preprocessed: bool = False, processes: int = None):
+    def __init__(self, preprocessed: bool = False, processes: int = None, batch_size: int = 1000):
         self.preprocessed = preprocessed
         self.processes = processes
+        self.batch_size = batch_size
 
     @property
     @lru_cache(maxsize=None)
@@ -54,10 +56,21 @@ class MapReduceLoader(Generic[TMap, TReduce], metaclass=abc.ABCMeta):
     def reduce(self, accum: TReduce, mapped: TMap) -> TReduce:
         pass
 
+    def parse_batch(self, rows: List[Dict[str, str]]) -> Iterator[Visit]:
+        if self.preprocessed:
+            yield from map(self.parse, rows)
+            return
+        # Parsed in parallel by the Rust library, with one shared cache
+        for visit in visualacuity.parse_visits(rows):
+            if isinstance(visit, Exception):
+                raise visit
+            yield visit
+
     def read_csv(self, *filenames):
         accum = None
         reader = MultiCsvReader(filenames, progress=self.progress)
-        jobs = self._maybe_parallel(self._parse_map, reader)
+        visits = (visit for batch in _batches(reader, self.batch_size) for visit in self.parse_batch(batch))
+        jobs = self._maybe_parallel(self.map, visits)
         for i, mapped in enumerate(jobs, start=1):
             accum = self.reduce(accum, mapped)
             self.callback(i, self.progress.total, mapped, accum)
@@ -68,9 +81,6 @@ class MapReduceLoader(Generic[TMap, TReduce], metaclass=abc.ABCMeta):
         self.progress.n = line_num
         self.progress.refresh()
 
-    def _parse_map(self, row):
-        return self.map(self.parse(row))
-
     @property
     def _maybe_parallel(self):
         if self.processes:
@@ -79,6 +89,12 @@ class MapReduceLoader(Generic[TMap, TReduce], metaclass=abc.ABCMeta):
             return map
 
 
+def _batches(iterable, size):
+    iterator = iter(iterable)
+    while batch := list(islice(iterator, size)):
+        yield batch
+
+
 class MultiCsvReader:
     def __init__(self, filenames, progress):
         self.filenames = filenames
@@ -0,0 +1,160 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use itertools::Itertools;
+
+use crate::charts::{Chart, Charts, LowVisionConvention, DEFAULT_CHART_ORDER};
+use crate::VisualAcuityError::ChartNotFound;
+use crate::{Parser, RangeConvention, VisualAcuityResult};
+
+/// Configures a `Parser` beyond the defaults of `Parser::new()`, e.g. with site-specific charts
+/// or a different chart priority.
+#[derive(Clone, Debug, Default)]
+pub struct ParserBuilder {
+    custom_charts: Vec<(String, ChartSource)>,
+    chart_order: Option<Vec<String>>,
+    column_chart_orders: BTreeMap<String, Vec<String>>,
+    low_vision_convention: Option<LowVisionConvention>,
+    range_convention: RangeConvention,
+}
+
+#[derive(Clone, Debug)]
+enum ChartSource {
+    Contents(Vec<String>),
+    Files(Vec<PathBuf>),
+}
+
+impl ParserBuilder {
+    /// Register a chart from the contents of one or more TSV files, in the same format as
+    /// `assets/charts/*.tsv`. Custom charts take priority over the predefined charts (unless
+    /// `chart_order` says otherwise), and replace any predefined chart with the same name.
+    pub fn chart<S: Into<String>>(
+        mut self,
+        name: &str,
+        contents: impl IntoIterator<Item = S>,
+    ) -> Self {
+        let contents = contents.into_iter().map(Into::into).collect();
+        let source = ChartSource::Contents(contents);
+        self.custom_charts.push((name.to_string(), source));
+        self
+    }
+
+    /// Like `chart`, but reading the TSV files from disk when the `Parser` is built
+    pub fn chart_files<P: Into<PathBuf>>(
+        mut self,
+        name: &str,
+        paths: impl IntoIterator<Item = P>,
+    ) -> Self {
+        let paths = paths.into_iter().map(Into::into).collect();
+        let source = ChartSource::Files(paths);
+        self.custom_charts.push((name.to_string(), source));
+        self
+    }
+
+    /// The charts to search (by name) when looking up a value, in order of priority. Charts left
+    /// out of the list aren't used.
+    pub fn chart_order<S: ToString>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.chart_order = Some(names.into_iter().map(|s| s.to_string()).collect());
+        self
+    }
+
+    /// Like `chart_order`, but only for the EHR field named `column` (ignoring case)
+    pub fn column_chart_order<S: ToString>(
+        mut self,
+        column: &str,
+        names: impl IntoIterator<Item = S>,
+    ) -> Self {
+        let names = names.into_iter().map(|s| s.to_string()).collect();
+        self.column_chart_orders
+            .insert(column.trim().to_lowercase(), names);
+        self
+    }
+
+    /// How to convert CF/HM/LP/NLP to LogMAR. The convention's chart takes the place of any
+    /// low-vision chart in the chart order(s).
+    pub fn low_vision_convention(mut self, convention: LowVisionConvention) -> Self {
+        self.low_vision_convention = Some(convention);
+        self
+    }
+
+    /// Which value to report for a range between two chart lines (e.g. "20/40-20/50"). Defaults
+    /// to the worse line.
+    pub fn range_convention(mut self, convention: RangeConvention) -> Self {
+        self.range_convention = convention;
+        self
+    }
+
+    /// Load the configured charts and build the `Parser`
+    pub fn build(self) -> VisualAcuityResult<Parser> {
+        let mut custom = BTreeMap::new();
+        for (name, source) in &self.custom_charts {
+            let contents = match source {
+                ChartSource::Contents(contents) => contents.clone(),
+                ChartSource::Files(paths) => paths.iter().map(read_file).try_collect()?,
+            };
+            custom.insert(name.clone(), Arc::new(Chart::parse_files(name, &contents)?));
+        }
+
+        let chart_order = self.chart_order.unwrap_or_else(|| {
+            // Custom charts first, then the predefined ones
+            let new_names = self
+                .custom_charts
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .filter(|name| !DEFAULT_CHART_ORDER.contains(name));
+            new_names
+                .chain(DEFAULT_CHART_ORDER)
+                .unique()
+                .map(String::from)
+                .collect()
+        });
+        if let Some(convention) = self.low_vision_convention {
+            // The convention replaces a low-vision chart, so at least one order must have one
+            let has_low_vision = |names: &Vec<String>| {
+                names
+                    .iter()
+                    .any(|name| LowVisionConvention::from_chart_name(name).is_some())
+            };
+            let mut orders = [&chart_order]
+                .into_iter()
+                .chain(self.column_chart_orders.values());
+            if !orders.any(has_low_vision) {
+                let name = convention.chart_name();
+                return Err(ChartNotFound(format!(
+                    "{name} (no low-vision chart to replace)"
+                )));
+            }
+        }
+        let load = |names: Vec<String>| {
+            let names = match self.low_vision_convention {
+                Some(convention) => names
+                    .into_iter()
+                    .map(|name| match LowVisionConvention::from_chart_name(&name) {
+                        Some(_) => convention.chart_name().to_string(),
+                        None => name,
+                    })
+                    .unique()
+                    .collect(),
+                None => names,
+            };
+            Charts::from_names(&names, &custom)
+        };
+        let charts = load(chart_order)?;
+
+        let column_charts = self
+            .column_chart_orders
+            .into_iter()
+            .map(|(column, names)| load(names).map(|c| (column, c)))
+            .try_collect()?;
+
+        Ok(Parser {
+            range_convention: self.range_convention,
+            ..Parser::with_charts(charts, column_charts)
+        })
+    }
+}
+
+fn read_file(path: &PathBuf) -> VisualAcuityResult<String> {
+    std::fs::read_to_string(path).map_err(|e| ChartNotFound(format!("{}: {e}", path.display())))
+}
@@ -1,34 +1,94 @@
 extern crate lru;
 
 use lru::LruCache;
-use std::cell::RefCell;
-use std::hash::Hash;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::num::NonZeroUsize;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 
+/// Number of independently-locked shards. Keys are spread across the shards by hash, so threads
+/// sharing one cache rarely wait on each other.
+const N_SHARDS: usize = 16;
+
+/// A thread-safe LRU cache. Each shard is a `Mutex<LruCache>` holding an even share of the
+/// total capacity.
 pub(crate) struct LruCacher<K, V> {
-    cache: RefCell<LruCache<K, Arc<V>>>,
+    shards: Vec<Mutex<LruCache<K, Arc<V>>>>,
 }
 
 impl<K: Clone + Hash + Eq, V: Clone> LruCacher<K, V> {
     pub(crate) fn new(cache_size: usize) -> Self {
-        let cache_size = NonZeroUsize::new(cache_size).unwrap();
-        let cache = RefCell::new(LruCache::new(cache_size));
-        Self { cache }
+        let n_shards = N_SHARDS.min(cache_size).max(1);
+        let shard_size = NonZeroUsize::new(cache_size.div_ceil(n_shards)).unwrap();
+        let shards = (0..n_shards)
+            .map(|_| Mutex::new(LruCache::new(shard_size)))
+            .collect();
+        Self { shards }
     }
 
     pub(crate) fn get<F>(&self, key: &K, func: F) -> V
     where
         F: Fn() -> V,
     {
-        let mut cache = self.cache.borrow_mut();
-        match cache.get(key) {
-            Some(cached_result) => V::clone(cached_result),
-            None => {
-                let computed = func();
-                cache.put(key.clone(), Arc::new(computed.clone()));
-                computed
+        if let Some(cached_result) = self.shard(key).get(key) {
+            return V::clone(cached_result);
+        }
+        // Compute without holding the lock, so a slow parse doesn't block other threads. Two
+        // threads may race to compute the same value, which is harmless.
+        let computed = func();
+        self.shard(key).put(key.clone(), Arc::new(computed.clone()));
+        computed
+    }
+
+    fn shard(&self, key: &K) -> MutexGuard<'_, LruCache<K, Arc<V>>> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let index = hasher.finish() as usize % self.shards.len();
+        // A panic can't leave an `LruCache` half-updated, so a poisoned lock is still usable.
+        self.shards[index]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+
+    #[test]
+    fn test_shared_between_threads() {
+        let cache = LruCacher::new(100);
+        let n_computed = AtomicUsize::new(0);
+        let compute = |n: usize| {
+            n_computed.fetch_add(1, Ordering::SeqCst);
+            n * 2
+        };
+
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for n in 0..10 {
+                        assert_eq!(cache.get(&n, || compute(n)), n * 2);
+                    }
+                });
             }
+        });
+
+        // Once warmed, nothing is recomputed
+        let before = n_computed.load(Ordering::SeqCst);
+        for n in 0..10 {
+            assert_eq!(cache.get(&n, || compute(n)), n * 2);
         }
+        assert_eq!(n_computed.load(Ordering::SeqCst), before);
+    }
+
+    #[test]
+    fn test_small_capacity() {
+        let cache = LruCacher::new(1);
+        assert_eq!(cache.get(&"a", || 1), 1);
+        assert_eq!(cache.get(&"b", || 2), 2);
+        assert_eq!(cache.get(&"a", || 3), 3, "Evicted by \"b\"");
     }
 }
@@ -1,3 +1,4 @@
+use crate::charts::{LowVisionConvention, Notation};
 use crate::logmar::LogMarBase;
 use crate::VisualAcuityError::{ChartNotFound, MultipleValues, ParseError};
 use crate::{DistanceUnits, Fraction};
@@ -6,44 +7,177 @@ use itertools::Itertools;
 use lazy_static::lazy_static;
 use std::collections::BTreeMap;
 use std::str::FromStr;
+use std::sync::Arc;
 
 lazy_static! {
     // Pre-load data from the chart definition files in ../../assets/charts
-    static ref PREDEFINED_CHARTS: BTreeMap<&'static str, Chart<'static>> = [
+    static ref PREDEFINED_CHARTS: BTreeMap<&'static str, Arc<Chart>> = [
             load_predefined("snellen", vec![
-                include_str!("../../assets/charts/snellen.feet.tsv"),
+                Notation::SnellenFeet.contents(),
             ]),
             load_predefined("bailey-lovie", vec![
-                include_str!("../../assets/charts/bailey-lovie.feet.tsv"),
-                include_str!("../../assets/charts/bailey-lovie.meters.tsv"),
+                Notation::BaileyLovieFeet.contents(),
+                Notation::BaileyLovieMeters.contents(),
             ]),
             load_predefined("jaeger", vec![
-                include_str!("../../assets/charts/jaeger.tsv"),
+                Notation::Jaeger.contents(),
             ]),
             load_predefined("teller", vec![
-                include_str!("../../assets/charts/teller.feet.tsv"),
-                include_str!("../../assets/charts/teller.cycm.tsv"),
-                include_str!("../../assets/charts/teller.card.tsv"),
+                Notation::TellerFeet.contents(),
+                Notation::TellerCyCm.contents(),
+                Notation::TellerCard.contents(),
             ]),
             load_predefined("schulze-bonzel", vec![
                 include_str!("../../assets/charts/schulze-bonzel.tnlv.tsv"),
             ]),
+            load_predefined("holladay", vec![
+                include_str!("../../assets/charts/holladay.tnlv.tsv"),
+            ]),
+            load_predefined("lange", vec![
+                include_str!("../../assets/charts/lange.tnlv.tsv"),
+            ]),
             load_predefined("etdrs", vec![
-                include_str!("../../assets/charts/etdrs.tsv"),
+                Notation::ETDRSLetters.contents(),
+            ]),
+            load_predefined("decimal", vec![
+                Notation::Decimal.contents(),
+                include_str!("../../assets/charts/decimal.monoyer.tsv"),
+            ]),
+            load_predefined("n-point", vec![
+                Notation::NPoint.contents(),
+            ]),
+            load_predefined("m-units", vec![
+                Notation::MUnits.contents(),
+            ]),
+            load_predefined("hotv", vec![
+                Notation::HOTV.contents(),
+            ]),
+            load_predefined("lea", vec![
+                Notation::LeaSymbols.contents(),
+            ]),
+            load_predefined("allen", vec![
+                Notation::AllenPictures.contents(),
+            ]),
+            load_predefined("kay", vec![
+                Notation::KayPictures.contents(),
+            ]),
+            load_predefined("cardiff", vec![
+                Notation::CardiffCards.contents(),
+            ]),
+            load_predefined("keeler", vec![
+                Notation::KeelerCards.contents(),
             ]),
         ].into_iter().try_collect().unwrap();
 
     // Establish an ordered lookup for the above charts. If a value occurs in multiple charts,
-    // prefer the first. This is hard-coded for now, but will probably become configurable
-    // by the API consumer as we come to understand what people need.
-    static ref ORDERED_CHARTS: Vec<&'static Chart<'static>> = vec![
-            Chart::load("snellen").unwrap(),
-            Chart::load("bailey-lovie").unwrap(),
-            Chart::load("jaeger").unwrap(),
-            Chart::load("teller").unwrap(),
-            Chart::load("schulze-bonzel").unwrap(),
-            Chart::load("etdrs").unwrap(),
-    ];
+    // prefer the first. A `Parser` can be built with a different order (see `ParserBuilder`).
+    pub(crate) static ref DEFAULT_CHARTS: Charts = Charts::from_names(
+        &DEFAULT_CHART_ORDER,
+        &BTreeMap::new()
+    ).unwrap();
+}
+
+/// How far (in LogMAR) a Snellen fraction with a non-standard test distance may be snapped to the
+/// nearest chart row, i.e. about one line
+const MAX_SNAP_LOG_MAR: f64 = 0.1;
+
+/// The longest non-standard test distance in a Snellen fraction (e.g. "10/200"), in feet
+const MAX_SCALED_TEST_DISTANCE: f64 = 20.0;
+
+/// Charts whose values don't depend on the test distance, i.e. grating acuity in cycles per degree
+const DISTANCE_INDEPENDENT_CHARTS: [&str; 1] = ["keeler"];
+
+pub(crate) const DEFAULT_CHART_ORDER: [&str; 15] = [
+    "snellen",
+    "bailey-lovie",
+    "jaeger",
+    "teller",
+    "schulze-bonzel",
+    "etdrs",
+    "decimal",
+    "n-point",
+    "m-units",
+    "hotv",
+    "lea",
+    "allen",
+    "kay",
+    "cardiff",
+    "keeler",
+];
+
+/// An ordered collection of `Chart`s to search when looking up a `ChartRow`. If a value occurs in
+/// multiple charts, the first one wins.
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) struct Charts(Vec<Arc<Chart>>);
+
+impl Charts {
+    /// Look up charts by name, preferring `custom` charts over the predefined ones
+    pub(crate) fn from_names<S: AsRef<str>>(
+        names: &[S],
+        custom: &BTreeMap<String, Arc<Chart>>,
+    ) -> VisualAcuityResult<Self> {
+        names
+            .iter()
+            .map(|name| match custom.get(name.as_ref()) {
+                Some(chart) => Ok(chart.clone()),
+                None => Chart::load(name.as_ref()),
+            })
+            .try_collect()
+            .map(Self)
+    }
+
+    /// Retrieve a `ChartRow` by normalized text from the first chart that contains it
+    pub(crate) fn find_row<S: ToString>(&self, value: &S) -> Option<&ChartRow> {
+        let value = value.to_string();
+        self.0
+            .iter()
+            .filter_map(|chart| chart.get_row(value.trim()))
+            .next()
+    }
+
+    /// For a Snellen fraction with a non-standard test distance (e.g. "10/200" or "3/60"), find
+    /// the row that was read from a chart with a standard distance ("20/200" or "6/60").
+    /// The numerator must be a whole test distance of up to 20 feet, or up to 6 meters if the
+    /// denominator is a row of a metric chart. Without an exact match, snap to the nearest row by
+    /// LogMAR.
+    pub(crate) fn find_scaled_row(&self, value: &str) -> Option<&ChartRow> {
+        let Fraction((distance, row)) = value.parse().ok()?;
+        if !(1.0..=MAX_SCALED_TEST_DISTANCE).contains(&distance)
+            || distance.fract() != 0.0
+            || [6.0, 20.0].contains(&distance)
+        {
+            // Standard fractions (e.g. "20/987") must be found on a chart as-is, and anything else
+            // (e.g. "120/80") isn't a test distance
+            return None;
+        }
+        let standards: &[f64] = if distance <= 6.0 {
+            &[6.0, 20.0]
+        } else {
+            &[20.0]
+        };
+        let exact = standards
+            .iter()
+            .find_map(|&standard| self.find_row(&Fraction((standard, row))));
+        exact.or_else(|| {
+            let standard = standards[0];
+            let log_mar = Fraction((standard, row)).log_mar_base().ok()?;
+            let prefix = format!("{standard}/");
+            self.0
+                .iter()
+                .flat_map(|chart| chart.by_text.values())
+                .filter(|chart_row| chart_row.text.starts_with(&prefix))
+                .filter_map(|chart_row| Some((chart_row, (log_mar - chart_row.log_mar?).abs())))
+                .filter(|&(_, residual)| residual <= MAX_SNAP_LOG_MAR)
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(chart_row, _)| chart_row)
+        })
+    }
+}
+
+impl Default for Charts {
+    fn default() -> Self {
+        DEFAULT_CHARTS.clone()
+    }
 }
 
 /// A `Chart` here is basically a ``data dictionary'' in which all the entries are related.
@@ -51,44 +185,49 @@ lazy_static! {
 /// relationships between the entries (e.g. for input text like "20/10 -2", computing LogMAR for
 /// partially-read lines requires information about two rows: 20/10 and 20/12.5)
 #[derive(Clone, PartialEq, Default, Debug)]
-pub struct Chart<'a> {
+pub struct Chart {
+    name: String,
     by_row_number: BTreeMap<i8, ChartRow>,
-    by_text: BTreeMap<&'a str, ChartRow>,
+    by_text: BTreeMap<String, ChartRow>,
 }
 
-impl<'a> Chart<'a> {
+impl Chart {
     /// Retrieve a `ChartRow` by its normalized "data dictionary" text
     pub(crate) fn get_row<S: ToString>(&self, text: S) -> Option<&ChartRow> {
-        self.by_text.get(&*text.to_string())
+        self.by_text.get(&text.to_string())
+    }
+
+    /// Iterate over the rows in order of row number
+    pub(crate) fn rows(&self) -> impl DoubleEndedIterator<Item = &ChartRow> {
+        self.by_row_number.values()
     }
 
-    /// Load a `Chart` from the contents of a TSV file
-    pub(crate) fn load(name: &'a str) -> VisualAcuityResult<&Self> {
+    /// Retrieve one of the predefined charts by name
+    pub(crate) fn load(name: &str) -> VisualAcuityResult<Arc<Self>> {
         match PREDEFINED_CHARTS.get(name) {
-            Some(chart) => Ok(chart),
-            None => {
-                // Here we could check whether there's a filename matching the string?
-                Err(ChartNotFound(name.to_string()))
-            }
+            Some(chart) => Ok(chart.clone()),
+            None => Err(ChartNotFound(name.to_string())),
         }
     }
 
-    pub(crate) fn parse_files(contents: Vec<(&'a str, &'a str)>) -> VisualAcuityResult<Self> {
-        let mut chart = Self::default();
+    /// Load a `Chart` from the contents of one or more TSV files
+    pub(crate) fn parse_files<S: AsRef<str>>(
+        name: &str,
+        contents: &[S],
+    ) -> VisualAcuityResult<Self> {
+        let mut chart = Self {
+            name: name.to_string(),
+            ..Self::default()
+        };
         let mut n_letters = BTreeMap::new();
         let mut log_mars = BTreeMap::new();
 
         // Collect up some parsed `ChartRow`s
         let rows: Vec<(i8, &str, ChartRow)> = contents
-            .into_iter()
-            .flat_map(|(chart_name, content)| {
-                map_tsv(content)
-                    .into_iter()
-                    .map(|r| (chart_name, r))
-                    .collect_vec()
-            })
-            .map(|(chart_name, (line, tsv_row))| {
-                parse_row(chart_name, tsv_row).map_err(|_| ParseError(line.to_string()))
+            .iter()
+            .flat_map(|content| map_tsv(content.as_ref()))
+            .map(|(line, tsv_row)| {
+                parse_row(name, tsv_row).map_err(|_| ParseError(line.to_string()))
             })
             .map_ok(|(row_number, row_text, chart_row)| {
                 n_letters.insert(row_number, chart_row.n_letters.clone());
@@ -113,13 +252,18 @@ impl<'a> Chart<'a> {
                 .flatten()
                 .unwrap_or_default();
 
+            // Rows from different files of the same chart (e.g. feet & meters) share a row number,
+            // and must agree on everything but the text
             let found = chart.by_row_number.insert(row_number, chart_row.clone());
+            let found = found.map(|found| ChartRow {
+                text: chart_row.text.clone(),
+                ..found
+            });
             if found.is_some() && found.as_ref() != Some(&chart_row) {
                 return Err(MultipleValues(format!("{chart_row:?}")));
             }
-            chart.by_text.insert(row_text, chart_row);
+            chart.by_text.insert(row_text.to_string(), chart_row);
         }
-        // }
         Ok(chart)
     }
 }
@@ -131,6 +275,7 @@ impl<'a> Chart<'a> {
 pub struct ChartRow {
     pub(crate) chart_name: String,
     pub(crate) row_number: i8,
+    pub(crate) text: String,
     pub(crate) fraction: Option<Fraction>,
     pub(crate) reference_distance: DistanceUnits,
     pub(crate) log_mar: Option<f64>,
@@ -141,13 +286,35 @@ pub struct ChartRow {
 }
 
 impl ChartRow {
-    /// Static lookup for a chart row by normalized text, searching all pre-loaded `Chart`
-    /// definitions. Chart priority is specified in `ORDERED_CHARTS` above.
-    pub(crate) fn find<'a, S: ToString>(value: &S) -> Option<&'a Self> {
-        ORDERED_CHARTS
-            .iter()
-            .filter_map(|chart| chart.get_row(value.to_string().as_str().trim()))
-            .next()
+    /// The name of the chart this row belongs to, e.g. "bailey-lovie"
+    pub fn chart_name(&self) -> &str {
+        &self.chart_name
+    }
+
+    /// The normalized text of this row, e.g. "20/40"
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The Snellen fraction for this row, if defined
+    pub fn fraction(&self) -> Option<Fraction> {
+        self.fraction
+    }
+
+    /// The LogMAR for this row, if defined
+    pub fn log_mar(&self) -> Option<f64> {
+        self.log_mar
+    }
+
+    /// The number of letters on this row, if defined
+    pub fn n_letters(&self) -> Option<u8> {
+        self.n_letters
+    }
+
+    /// Whether this row's value holds at any test distance (e.g. "19 cpd", or "CF" with Lange)
+    pub(crate) fn is_distance_independent(&self) -> bool {
+        DISTANCE_INDEPENDENT_CHARTS.contains(&self.chart_name.as_str())
+            || LowVisionConvention::has_fixed_values(&self.chart_name)
     }
 }
 
@@ -157,9 +324,11 @@ fn parse_row<'a>(
 ) -> VisualAcuityResult<(i8, &'a str, ChartRow)> {
     let row_number = row
         .get("Row")
-        .expect("Must contain a row number!")
+        .ok_or_else(|| ParseError("Must contain a row number!".to_string()))?
         .parse()?;
-    let row_text = row.get("Text").expect("Must contain text!");
+    let row_text = row
+        .get("Text")
+        .ok_or_else(|| ParseError("Must contain text!".to_string()))?;
     let fraction = parse_some(nonempty(row.get("Fraction").cloned()))?;
     let log_mar = row.get("LogMAR").map(|&s| s.trim_start_matches('+'));
     let log_mar = parse_some(nonempty(log_mar))?;
@@ -171,9 +340,11 @@ fn parse_row<'a>(
     let next_log_mar = None;
     let next_n_letters = None;
     let chart_name = chart_name.to_string();
+    let text = row_text.to_string();
     let chart_row = ChartRow {
         chart_name,
         row_number,
+        text,
         fraction,
         reference_distance,
         log_mar,
@@ -215,18 +386,17 @@ fn fill_in_log_mar(
     }
 }
 
-fn load_predefined<'a>(
-    name: &'a str,
-    filenames: Vec<&'a str>,
-) -> VisualAcuityResult<(&'a str, Chart<'a>)> {
-    let contents = filenames.into_iter().map(|f| (name, f)).collect();
-    let result = Chart::parse_files(contents);
-    Ok((name, result?))
+fn load_predefined(
+    name: &'static str,
+    contents: Vec<&'static str>,
+) -> VisualAcuityResult<(&'static str, Arc<Chart>)> {
+    let result = Chart::parse_files(name, &contents);
+    Ok((name, Arc::new(result?)))
 }
 
 pub fn map_tsv(contents: &str) -> Vec<(&str, BTreeMap<&str, &str>)> {
     let mut lines = contents.lines().into_iter();
-    let header = lines.next().expect("File can't have no lines!").split("\t");
+    let header = lines.next().unwrap_or_default().split("\t");
     lines
         .filter_map(|line| match line.trim() {
             "" => None,
@@ -1,12 +1,97 @@
 pub use chart::ChartRow;
+pub(crate) use chart::{Chart, Charts, DEFAULT_CHARTS, DEFAULT_CHART_ORDER};
+pub use notation::{NearestRow, Notation};
+
+use crate::{DistanceUnits, Fraction, ParsedItem, VisualAcuityResult};
 
 mod chart;
+mod notation;
+
+/// Published conventions for converting near-total vision loss (CF/HM/LP/NLP) to LogMAR. Each
+/// corresponds to one of the predefined charts.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LowVisionConvention {
+    /// Schulze-Bonzel et al. (2006): CF and HM only, measured at a distance
+    #[default]
+    SchulzeBonzel,
+    /// Holladay (1997): CF and HM only, measured at a distance
+    Holladay,
+    /// Lange et al. (2009): fixed values for CF, HM, LP and NLP, regardless of distance
+    Lange,
+}
+
+impl LowVisionConvention {
+    const ALL: [Self; 3] = [Self::SchulzeBonzel, Self::Holladay, Self::Lange];
+
+    pub(crate) fn chart_name(&self) -> &'static str {
+        match self {
+            Self::SchulzeBonzel => "schulze-bonzel",
+            Self::Holladay => "holladay",
+            Self::Lange => "lange",
+        }
+    }
+
+    pub(crate) fn from_chart_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.chart_name() == name)
+    }
+
+    /// Whether the chart has fixed values for low vision, regardless of the test distance
+    pub(crate) fn has_fixed_values(chart_name: &str) -> bool {
+        Self::from_chart_name(chart_name) == Some(Self::Lange)
+    }
+}
+
+/// A `ParsedItem` paired with the `Charts` that should be used to convert it
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ChartedItem<'a> {
+    pub(crate) item: &'a ParsedItem,
+    pub(crate) charts: &'a Charts,
+}
+
+impl<'a> ChartedItem<'a> {
+    pub(crate) fn find_chart_row(&self) -> VisualAcuityResult<&'a ChartRow> {
+        self.item.find_chart_row(self.charts)
+    }
+
+    /// The distance this item was measured at. Unless noted otherwise, charts are read at their
+    /// reference distance, except for near-total vision loss (CF/HM/etc.).
+    pub(crate) fn measurement_distance(&self, row: &'a ChartRow) -> &'a DistanceUnits {
+        match (self.item, self.item.measurement_distance()) {
+            (ParsedItem::NearTotalLoss(..), distance) => distance,
+            (_, DistanceUnits::NotProvided) => &row.reference_distance,
+            (_, distance) => distance,
+        }
+    }
+
+    /// The number of letters on the chart row that was read, if the chart says
+    pub(crate) fn n_letters(&self) -> Option<u8> {
+        match self.find_scaled_row() {
+            Some((_, row)) => row.n_letters,
+            None => self.find_chart_row().ok()?.n_letters,
+        }
+    }
+
+    /// For a Snellen fraction with a non-standard test distance (e.g. "10/200"), the fraction and
+    /// the chart row that was read at that distance (e.g. "20/200")
+    pub(crate) fn find_scaled_row(&self) -> Option<(Fraction, &'a ChartRow)> {
+        let ParsedItem::SnellenFraction(s) = self.item else {
+            return None;
+        };
+        if self.find_chart_row().is_ok() {
+            return None;
+        }
+        Some((s.parse().ok()?, self.charts.find_scaled_row(s)?))
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::helpers::RoundPlaces;
     use crate::VisualAcuityResult;
     use chart::Chart;
+    use test_case::test_case;
+    use Notation::*;
 
     #[test]
     fn test_load() -> VisualAcuityResult<()> {
@@ -18,4 +103,31 @@ mod tests {
         assert_eq!(actual, Some(-0.1));
         Ok(())
     }
+
+    #[test_case(SnellenFeet, 0.301, "20/40", 0.0)]
+    #[test_case(SnellenFeet, 0.33, "20/40", 0.029)]
+    #[test_case(BaileyLovieMeters, 0.2, "6/9.5", 0.0)]
+    #[test_case(Jaeger, 0.4, "J6", 0.002)]
+    #[test_case(ETDRSLetters, 0.0, "85 letters", 0.0)]
+    #[test_case(ETDRSLetters, 0.12, "80 letters", 0.02)]
+    #[test_case(TellerCyCm, 0.0, "38 cy/cm", -0.061)]
+    fn test_nearest_row(
+        notation: Notation,
+        log_mar: f64,
+        expected_text: &str,
+        expected_residual: f64,
+    ) -> VisualAcuityResult<()> {
+        let NearestRow { row, residual } = notation.nearest_row(log_mar)?;
+        assert_eq!(row.text(), expected_text);
+        assert_eq!(residual.round_places(3), expected_residual);
+        Ok(())
+    }
+
+    #[test]
+    fn test_nearest_row_to_fraction() -> VisualAcuityResult<()> {
+        let nearest = BaileyLovieMeters.nearest_row_to_fraction((20, 200).into())?;
+        assert_eq!(nearest.row.text(), "6/60");
+        assert_eq!(nearest.row.chart_name(), "bailey-lovie");
+        Ok(())
+    }
 }
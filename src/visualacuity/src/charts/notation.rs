@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use lazy_static::lazy_static;
+
+use crate::charts::{Chart, ChartRow};
+use crate::logmar::LogMarBase;
+use crate::VisualAcuityError::{ChartNotFound, NoValue};
+use crate::{Fraction, VisualAcuityResult};
+
+lazy_static! {
+    // Each notation is loaded as a chart of its own, so that every row has that notation's text
+    static ref NOTATION_CHARTS: BTreeMap<Notation, Arc<Chart>> = Notation::ALL
+        .into_iter()
+        .map(|notation| {
+            let chart = Chart::parse_files(notation.chart_name(), &[notation.contents()])?;
+            Ok((notation, Arc::new(chart)))
+        })
+        .collect::<VisualAcuityResult<_>>()
+        .unwrap();
+}
+
+/// A way of writing down visual acuity, i.e. a single chart definition file from
+/// `assets/charts`. Used to express an acuity in the terms of a particular chart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Notation {
+    /// e.g. "20/40"
+    SnellenFeet,
+    /// e.g. "20/32"
+    BaileyLovieFeet,
+    /// e.g. "6/9.5"
+    BaileyLovieMeters,
+    /// e.g. "J5"
+    Jaeger,
+    /// e.g. "70 letters"
+    ETDRSLetters,
+    /// e.g. "20/45"
+    TellerFeet,
+    /// e.g. "19 cy/cm"
+    TellerCyCm,
+    /// e.g. "Card 15"
+    TellerCard,
+    /// e.g. "0.5"
+    Decimal,
+    /// e.g. "N8"
+    NPoint,
+    /// e.g. "1.0M"
+    MUnits,
+    /// e.g. "HOTV 20/32"
+    HOTV,
+    /// e.g. "Lea 10/16"
+    LeaSymbols,
+    /// e.g. "Allen 20/30"
+    AllenPictures,
+    /// e.g. "Kay 6/9"
+    KayPictures,
+    /// e.g. "Cardiff 6/24"
+    CardiffCards,
+    /// e.g. "4.8 cpd"
+    KeelerCards,
+}
+
+/// The `ChartRow` closest to a given acuity
+#[derive(Clone, Debug, PartialEq)]
+pub struct NearestRow {
+    pub row: ChartRow,
+    /// The LogMAR of the given acuity minus the LogMAR of `row`
+    pub residual: f64,
+}
+
+impl Notation {
+    const ALL: [Self; 17] = [
+        Self::SnellenFeet,
+        Self::BaileyLovieFeet,
+        Self::BaileyLovieMeters,
+        Self::Jaeger,
+        Self::ETDRSLetters,
+        Self::TellerFeet,
+        Self::TellerCyCm,
+        Self::TellerCard,
+        Self::Decimal,
+        Self::NPoint,
+        Self::MUnits,
+        Self::HOTV,
+        Self::LeaSymbols,
+        Self::AllenPictures,
+        Self::KayPictures,
+        Self::CardiffCards,
+        Self::KeelerCards,
+    ];
+
+    /// The name of the predefined chart this notation belongs to
+    pub(crate) fn chart_name(&self) -> &'static str {
+        match self {
+            Self::SnellenFeet => "snellen",
+            Self::BaileyLovieFeet | Self::BaileyLovieMeters => "bailey-lovie",
+            Self::Jaeger => "jaeger",
+            Self::ETDRSLetters => "etdrs",
+            Self::TellerFeet | Self::TellerCyCm | Self::TellerCard => "teller",
+            Self::Decimal => "decimal",
+            Self::NPoint => "n-point",
+            Self::MUnits => "m-units",
+            Self::HOTV => "hotv",
+            Self::LeaSymbols => "lea",
+            Self::AllenPictures => "allen",
+            Self::KayPictures => "kay",
+            Self::CardiffCards => "cardiff",
+            Self::KeelerCards => "keeler",
+        }
+    }
+
+    pub(crate) fn contents(&self) -> &'static str {
+        match self {
+            Self::SnellenFeet => include_str!("../../assets/charts/snellen.feet.tsv"),
+            Self::BaileyLovieFeet => include_str!("../../assets/charts/bailey-lovie.feet.tsv"),
+            Self::BaileyLovieMeters => include_str!("../../assets/charts/bailey-lovie.meters.tsv"),
+            Self::Jaeger => include_str!("../../assets/charts/jaeger.tsv"),
+            Self::ETDRSLetters => include_str!("../../assets/charts/etdrs.tsv"),
+            Self::TellerFeet => include_str!("../../assets/charts/teller.feet.tsv"),
+            Self::TellerCyCm => include_str!("../../assets/charts/teller.cycm.tsv"),
+            Self::TellerCard => include_str!("../../assets/charts/teller.card.tsv"),
+            Self::Decimal => include_str!("../../assets/charts/decimal.tsv"),
+            Self::NPoint => include_str!("../../assets/charts/n-point.tsv"),
+            Self::MUnits => include_str!("../../assets/charts/m-units.tsv"),
+            Self::HOTV => include_str!("../../assets/charts/hotv.tsv"),
+            Self::LeaSymbols => include_str!("../../assets/charts/lea.tsv"),
+            Self::AllenPictures => include_str!("../../assets/charts/allen.tsv"),
+            Self::KayPictures => include_str!("../../assets/charts/kay.tsv"),
+            Self::CardiffCards => include_str!("../../assets/charts/cardiff.tsv"),
+            Self::KeelerCards => include_str!("../../assets/charts/keeler.tsv"),
+        }
+    }
+
+    /// Find the row of this notation's chart with the closest LogMAR. Rows without a LogMAR are
+    /// skipped, and ties go to the higher row number (e.g. a full line of ETDRS letters).
+    pub fn nearest_row(&self, log_mar: f64) -> VisualAcuityResult<NearestRow> {
+        let chart = NOTATION_CHARTS
+            .get(self)
+            .ok_or_else(|| ChartNotFound(format!("{self:?}")))?;
+        chart
+            .rows()
+            .rev()
+            .filter_map(|row| Some((row, log_mar - row.log_mar?)))
+            .min_by(|(_, a), (_, b)| a.abs().total_cmp(&b.abs()))
+            .map(|(row, residual)| NearestRow {
+                row: row.clone(),
+                residual,
+            })
+            .ok_or(NoValue)
+    }
+
+    /// Like `nearest_row`, starting from a Snellen fraction
+    pub fn nearest_row_to_fraction(&self, fraction: Fraction) -> VisualAcuityResult<NearestRow> {
+        self.nearest_row(fraction.log_mar_base()?)
+    }
+}
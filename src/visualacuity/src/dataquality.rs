@@ -7,4 +7,8 @@ pub enum DataQuality {
     Multiple = 3,
     ConvertibleConfident = 4,
     ConvertibleFuzzy = 5,
+    Range = 6,
+    /// Several observations in one field (e.g. "OD 20/40 OS 20/30"), each with its own values
+    /// in `VisitNote::observations`
+    Observations = 7,
 }
@@ -46,6 +46,9 @@ impl RoundPlaces for VisitNote {
         let mut result = self.clone();
         result.log_mar_base = result.log_mar_base.round_places(p);
         result.log_mar_base_plus_letters = result.log_mar_base_plus_letters.round_places(p);
+        result.decimal_acuity = result.decimal_acuity.round_places(p);
+        result.minimum_angle_of_resolution = result.minimum_angle_of_resolution.round_places(p);
+        result.visual_acuity_score = result.visual_acuity_score.round_places(p);
         result
     }
 }
@@ -1,22 +1,26 @@
 extern crate lalrpop_util;
 extern crate visualacuity_proc_macro;
 
-use itertools::Itertools;
 use lazy_static::lazy_static;
+use std::collections::BTreeMap;
 
+pub use builder::ParserBuilder;
+pub use charts::{ChartRow, LowVisionConvention, NearestRow, Notation};
 pub use dataquality::DataQuality;
 pub use distanceunits::*;
 pub use structure::*;
-pub use visit::{EntryMetadata, Visit, VisitNote};
+pub use visit::{EntryMetadata, PartialVisit, Visit, VisitNote};
 use visitinput::ColumnMerger;
 pub use visitinput::VisitInput;
 
 use crate::cache::LruCacher;
+use crate::charts::Charts;
 pub use crate::errors::{OptionResult, VisualAcuityError, VisualAcuityResult};
 use crate::parser::*;
 pub use crate::visit::metadata::*;
 use crate::ParsedItem::*;
 
+mod builder;
 mod cache;
 mod charts;
 mod distanceunits;
@@ -42,10 +46,22 @@ pub struct Parser {
     // parse_cache: LruCacher<String, (DataQuality, ParsedItemCollection)>,
     // key_cache: LruCacher<String, VisualAcuityResult<EntryMetadata>>,
     column_merger: ColumnMerger,
+    charts: Charts,
+    column_charts: BTreeMap<String, Charts>,
+    range_convention: RangeConvention,
 }
 
 impl Parser {
     pub fn new() -> Self {
+        Self::with_charts(Charts::default(), BTreeMap::new())
+    }
+
+    /// Configure a `Parser`, e.g. with custom charts. See `ParserBuilder`.
+    pub fn builder() -> ParserBuilder {
+        ParserBuilder::default()
+    }
+
+    pub(crate) fn with_charts(charts: Charts, column_charts: BTreeMap<String, Charts>) -> Self {
         lazy_static! {
             static ref CHART_NOTES_PARSER: ChartNotesParser = ChartNotesParser::new();
             static ref KEY_PARSER: KeyParser = KeyParser::new();
@@ -60,26 +76,55 @@ impl Parser {
             key_parser: &KEY_PARSER,
             cache,
             column_merger,
+            charts,
+            column_charts,
+            range_convention: RangeConvention::default(),
         }
     }
 
     pub fn parse_visit(&self, visit_notes: VisitInput) -> VisualAcuityResult<Visit> {
-        use VisualAcuityError::*;
+        self.parse_visit_partial(visit_notes).try_into()
+    }
 
-        let merged = self.column_merger.merge_plus_columns(visit_notes.into());
-        let (parsed_visit_notes, errors): (_, Vec<_>) = merged
+    /// Parse a visit, keeping a separate result for each EHR field. Unlike `parse_visit`, a field
+    /// that fails to parse doesn't discard the other fields.
+    pub fn parse_visit_partial(&self, visit_notes: VisitInput) -> PartialVisit {
+        let merged = self.column_merger.merge_plus_columns(visit_notes);
+        let parsed_visit_notes = merged
             .into_iter()
             .map(|(key, (text, text_plus))| {
-                let visit_note = self.parse_visit_note(key.as_str(), (&*text, &*text_plus))?;
-                Ok((key, visit_note))
+                let visit_note = self.parse_visit_note(key.as_str(), (&*text, &*text_plus));
+                (key, visit_note)
             })
-            .partition_result();
+            .collect();
 
-        if errors.len() > 0 {
-            return Err(MultipleErrors(errors));
-        }
+        PartialVisit(parsed_visit_notes).resolve_cross_references()
+    }
 
-        Ok(Visit(parsed_visit_notes))
+    /// Parse a batch of visits, returning one result per visit in input order. With the `rayon`
+    /// feature enabled, visits are parsed in parallel, with all workers sharing this parser's
+    /// cache.
+    pub fn parse_visits<I>(&self, visits: I) -> Vec<VisualAcuityResult<Visit>>
+    where
+        I: IntoIterator,
+        I::Item: Into<VisitInput> + Send,
+    {
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            let visits = visits.into_iter().collect::<Vec<_>>();
+            visits
+                .into_par_iter()
+                .map(|visit_notes| self.parse_visit(visit_notes.into()))
+                .collect()
+        }
+        #[cfg(not(feature = "rayon"))]
+        {
+            visits
+                .into_iter()
+                .map(|visit_notes| self.parse_visit(visit_notes.into()))
+                .collect()
+        }
     }
 
     fn parse_visit_note(
@@ -102,25 +147,43 @@ impl Parser {
         );
 
         self.cache.get(&cache_key, || {
-            let parsed_text = self.parse_text(text);
-            let parsed_text_plus = self.parse_text(text_plus);
+            let charts = self.charts_for(&cache_key.0);
+            let parsed_text = self.parse_text(text, charts);
+            let parsed_text_plus = self.parse_text(text_plus, charts);
             let visit_metadata = self.parse_key(key)?;
-            VisitNote::new(visit_metadata, parsed_text, parsed_text_plus).map(Some)
+            VisitNote::new(
+                visit_metadata,
+                parsed_text,
+                parsed_text_plus,
+                charts,
+                self.range_convention,
+            )
+            .map(Some)
         })
     }
 
-    fn parse_text<'input>(&self, notes: &'input str) -> Content<'input, ParsedItemCollection> {
+    /// The charts to use for an EHR field, given its lowercased name
+    fn charts_for(&self, key: &str) -> &Charts {
+        self.column_charts.get(key).unwrap_or(&self.charts)
+    }
+
+    fn parse_text<'input>(
+        &self,
+        notes: &'input str,
+        charts: &Charts,
+    ) -> Content<'input, Vec<Content<'input, ParsedItem>>> {
         let notes = notes.trim();
-        let (dq, content) = match self.notes_parser.parse(notes) {
+        let (dq, content) = match self.notes_parser.parse_with_charts(notes, charts) {
             Ok(Content {
                 content,
                 data_quality: dq,
                 ..
             }) => (dq, content),
-            Err(e) => (
-                DataQuality::ConvertibleFuzzy,
-                ParsedItemCollection(vec![Unhandled(format!(" {e}"))]),
-            ),
+            Err(e) => {
+                let dq = DataQuality::ConvertibleFuzzy;
+                let unhandled = Content::new(Unhandled(format!(" {e}")), notes, dq.clone());
+                (dq, vec![unhandled])
+            }
         };
         Content::new(content, notes, dq)
     }
@@ -1,4 +1,4 @@
-use crate::charts::ChartRow;
+use crate::charts::{ChartRow, ChartedItem, DEFAULT_CHARTS};
 use crate::snellen_equivalent::SnellenEquivalent;
 use crate::VisualAcuityError::*;
 use crate::{DistanceUnits, Fraction, ParsedItem, VisualAcuityResult};
@@ -8,18 +8,35 @@ pub(crate) trait LogMarBase {
 }
 
 impl LogMarBase for ParsedItem {
+    fn log_mar_base(&self) -> VisualAcuityResult<f64> {
+        self.with_charts(&DEFAULT_CHARTS).log_mar_base()
+    }
+}
+
+impl LogMarBase for ChartedItem<'_> {
     fn log_mar_base(&self) -> VisualAcuityResult<f64> {
         use DistanceUnits::*;
+        if let ParsedItem::LogMar(log_mar) = self.item {
+            // Recorded directly, no chart needed
+            return Ok(log_mar.parse()?);
+        }
+        if let Some((fraction, _)) = self.find_scaled_row() {
+            // e.g. "10/200": the fraction already accounts for the test distance
+            return fraction.log_mar_base();
+        }
         let row = self.find_chart_row()?;
         let log_mar_base = row.log_mar_base()?;
 
-        let meas_dist = self.measurement_distance();
+        let meas_dist = self.measurement_distance(row);
         let ref_dist = &row.reference_distance;
 
         // Do we need to scale to a measured distance?
         match (meas_dist, ref_dist) {
             // No
             (NotProvided, NotProvided) => Ok(log_mar_base),
+            // No, for values that don't depend on the test distance, i.e. grating acuity in
+            // cycles per degree or fixed low-vision values (e.g. "CF @ 2ft" with Lange)
+            (_, NotProvided) if row.is_distance_independent() => Ok(log_mar_base),
             // Yes
             _ => {
                 let Ok(m) = meas_dist.to_feet() else {
@@ -61,18 +78,56 @@ impl LogMarBase for Fraction {
     }
 }
 
+/// Other common scales for visual acuity, each derived from LogMAR
+pub(crate) trait LogMarScales {
+    /// Decimal acuity, e.g. 0.5 for 20/40
+    fn decimal_acuity(&self) -> f64;
+    /// Minimum angle of resolution, in minutes of arc
+    fn minimum_angle_of_resolution(&self) -> f64;
+    /// Visual Acuity Score (ETDRS-style letter score), i.e. 100 - 50 * LogMAR
+    fn visual_acuity_score(&self) -> f64;
+}
+
+impl LogMarScales for f64 {
+    fn decimal_acuity(&self) -> f64 {
+        1.0 / self.minimum_angle_of_resolution()
+    }
+
+    fn minimum_angle_of_resolution(&self) -> f64 {
+        10.0_f64.powf(*self)
+    }
+
+    fn visual_acuity_score(&self) -> f64 {
+        100.0 - 50.0 * self
+    }
+}
+
 pub trait LogMarPlusLetters {
     fn log_mar_plus_letters(&self, plus_letters: &Vec<i32>) -> VisualAcuityResult<f64>;
 }
 
 impl LogMarPlusLetters for ParsedItem {
+    fn log_mar_plus_letters(&self, plus_letters: &Vec<i32>) -> VisualAcuityResult<f64> {
+        self.with_charts(&DEFAULT_CHARTS)
+            .log_mar_plus_letters(plus_letters)
+    }
+}
+
+impl LogMarPlusLetters for ChartedItem<'_> {
     fn log_mar_plus_letters(&self, plus_letters: &Vec<i32>) -> VisualAcuityResult<f64> {
         if plus_letters.len() == 0 {
             return self.log_mar_base();
         }
-        self.find_chart_row()
-            .and_then(|row| row.log_mar_plus_letters(plus_letters))
-            .map_err(|row| ChartRowNotFound(row.to_string()))
+        if let ParsedItem::LogMar(_) = self.item {
+            // There's no chart row to read a partial line from
+            return Err(PlusLettersNotAllowed);
+        }
+        if let Some((fraction, row)) = self.find_scaled_row() {
+            // Partial lines are counted on the row that was read
+            let increment = row.log_mar_plus_letters(plus_letters)? - row.log_mar_base()?;
+            return Ok(fraction.log_mar_base()? + increment);
+        }
+        self.find_chart_row()?.log_mar_plus_letters(plus_letters)
     }
 }
 
@@ -87,6 +142,10 @@ impl LogMarPlusLetters for ChartRow {
         if plus_letters.len() == 0 {
             return self.log_mar_base();
         }
+        if self.n_letters.is_none() {
+            // e.g. Monoyer decimal rows, which don't have a set number of letters
+            return Err(PlusLettersNotAllowed);
+        }
         fn pos(row: &ChartRow) -> Option<f64> {
             Some((row.next_log_mar? - row.log_mar?) / row.next_n_letters? as f64)
         }
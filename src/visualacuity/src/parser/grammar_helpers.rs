@@ -7,28 +7,59 @@ use lalrpop_util::lexer::Token;
 use lalrpop_util::ErrorRecovery;
 use lalrpop_util::ParseError::{UnrecognizedEof, UnrecognizedToken};
 
-use crate::charts::ChartRow;
+use crate::charts::Charts;
 use crate::parser::decorator::Content;
-use crate::ParsedItem::{Text, Unhandled};
+use crate::ParsedItem::{
+    AcuityRange, CrossReferenceItem, PartialLineItem, SnellenFraction, Text, Unhandled,
+};
 use crate::VisualAcuityError::ParseError;
-use crate::{ParsedItem, ParsedItemCollection, VisualAcuityResult};
+use crate::{DataQuality, ParsedItem, PartialLine, VisualAcuityResult};
 
 pub(crate) fn merge_consecutive_texts<'a>(
     items: Vec<Content<'a, ParsedItem>>,
-) -> Content<'a, ParsedItemCollection> {
+    charts: &Charts,
+) -> Content<'a, Vec<Content<'a, ParsedItem>>> {
     items
         .into_iter()
-        .map(validate)
+        .map(|item| validate(item, charts))
         .fold(vec![], |mut accum, next| {
             if next.content == Text(String::default()) {
                 return accum;
             }
+            // e.g. "CF x3": "x" only counts missed letters right after a fraction ("20/40 x2")
+            let next = match accum.last() {
+                Some(Content {
+                    content: SnellenFraction(_),
+                    ..
+                }) => next,
+                _ if is_times_missed(&next) => next.map(|_| Text(next.input_string())),
+                _ => next,
+            };
             let Some(prev) = accum.pop() else {
                 accum.push(next);
                 return accum;
             };
             let to_append = match (&prev.content, &next.content) {
                 (Text(..), Text(..)) => vec![merge_text(prev, next)],
+                // e.g. "same as last visit" refers to an earlier visit, not another field of this one
+                (CrossReferenceItem(_), Text(s))
+                    if s.split_whitespace()
+                        .next()
+                        .is_some_and(|w| w.eq_ignore_ascii_case("as")) =>
+                {
+                    vec![merge_text(prev, next)]
+                }
+                // e.g. "20/40 3/5" => letters read out of the 20/40 line, rather than two fractions
+                (SnellenFraction(_), SnellenFraction(s) | Text(s))
+                    if letters_of_fraction(s).is_some() =>
+                {
+                    let partial_line = Content {
+                        content: PartialLineItem(letters_of_fraction(s).unwrap()),
+                        data_quality: DataQuality::NoValue,
+                        ..next
+                    };
+                    vec![prev, partial_line]
+                }
                 _ => vec![prev, next],
             };
             to_append
@@ -38,22 +69,57 @@ pub(crate) fn merge_consecutive_texts<'a>(
             accum
         })
         .into_iter()
+        // Keep each item's own span, nested inside the span of the whole collection
+        .map(|item| item.map(|_| item.clone()))
         .collect()
 }
 
-fn validate<'a>(input: Content<'a, ParsedItem>) -> Content<'a, ParsedItem> {
+/// Whether the item is missed letters written like "x2"
+fn is_times_missed(item: &Content<ParsedItem>) -> bool {
+    matches!(item.content, PartialLineItem(PartialLine::Missed(_)))
+        && item.input_string().to_lowercase().starts_with('x')
+}
+
+fn validate<'a>(input: Content<'a, ParsedItem>, charts: &Charts) -> Content<'a, ParsedItem> {
     /// Turn a ParsedItem back into ParsedItem::Text() if it's not a valid chart row
     use ParsedItem::*;
     match &input.content {
-        SnellenFraction(s) | Jaeger(s) | Teller(s) | ETDRS(s) | NearTotalLoss(s, _) => {
-            match ChartRow::find(s) {
-                None => input.map(|_| Text(input.input_string())),
-                Some(_) => input,
-            }
-        }
+        // A fraction with a non-standard test distance (e.g. "10/200") is kept if it scales to a chart row
+        SnellenFraction(s) => match charts.find_row(s).or_else(|| charts.find_scaled_row(s)) {
+            None => input.map(|_| Text(input.input_string())),
+            Some(_) => input,
+        },
+        Jaeger(s)
+        | Teller(s, _)
+        | ETDRS(s, _)
+        | Decimal(s)
+        | NearTotalLoss(s, _)
+        | NearCard(s, _)
+        | Keeler(s, _) => match charts.find_row(s) {
+            None => input.map(|_| Text(input.input_string())),
+            Some(_) => input,
+        },
+        OptotypeItem(..) => match input.content.chart_row_key() {
+            Ok(key) if charts.find_row(&key).is_some() => input,
+            _ => input.map(|_| Text(input.input_string())),
+        },
+        LogMar(_) => input,
         VisualResponse(_) => input,
+        FixationItem(_) => input,
         CrossReferenceItem(_) => input,
         PlusLettersItem(_) => input,
+        PartialLineItem(_) => input,
+        QualifierItem(_) => input,
+        AcuityRange(a, b) => {
+            let valid = |item: &ParsedItem| {
+                let validated = validate(input.map(|_| item.clone()), charts);
+                !matches!(validated.content, Text(_))
+            };
+            match valid(a) && valid(b) {
+                true => input,
+                false => input.map(|_| Text(input.input_string())),
+            }
+        }
         NotTakenItem(_) => input,
         DistanceItem(_) => input,
         LateralityItem(_) => input,
@@ -78,6 +144,66 @@ fn merge_text<'a>(
     }
 }
 
+/// Format a decimal acuity the way it's written on the decimal chart, e.g. "1.0" or "0.63"
+pub(crate) fn format_decimal(n: f64) -> String {
+    if n.fract() == 0.0 {
+        format!("{n:.1}")
+    } else {
+        format!("{n}")
+    }
+}
+
+/// Format a LogMAR value to (at least) two decimal places, e.g. 0.3 => "0.30"
+pub(crate) fn format_log_mar(n: f64) -> String {
+    let n = if n == 0.0 { 0.0 } else { n }; // No "-0.00"
+    let formatted = format!("{n:.2}");
+    match formatted.parse::<f64>() {
+        Ok(rounded) if rounded == n => formatted,
+        _ => format!("{n}"),
+    }
+}
+
+/// The ETDRS letter score for letters read at 4m and then at 1m, e.g. "12 @ 4m + 25 @ 1m" => 37.
+/// The 1m letters only count when fewer than 20 were read at 4m, otherwise all 30 are credited.
+pub(crate) fn etdrs_combined_score(s: &str) -> VisualAcuityResult<u64> {
+    let (at_4m, _, at_1m, _): (u64, u64, u64, u64) = extract_floats(s)?;
+    Ok(at_4m + if at_4m < 20 { at_1m } else { 30 })
+}
+
+/// A range between two Snellen fractions, e.g. "20/40 to 20/50"
+pub(crate) fn snellen_range(s: &str) -> VisualAcuityResult<ParsedItem> {
+    let (n1, d1, n2, d2): (f64, f64, f64, f64) = extract_floats(s)?;
+    let a = SnellenFraction(format!("{n1}/{d1}"));
+    let b = SnellenFraction(format!("{n2}/{d2}"));
+    Ok(AcuityRange(Box::new(a), Box::new(b)))
+}
+
+/// Letters read out of a line, e.g. "4 of 5" => 4/5, and "-2 of 5" => 3/5
+pub(crate) fn parse_letters_of(s: &str) -> VisualAcuityResult<PartialLine> {
+    let (n, of): (u8, u8) = extract_floats(s)?;
+    match s.trim_start().starts_with('-') {
+        false if n <= of => Ok(PartialLine::Read(n, of)),
+        true if n <= of => Ok(PartialLine::Read(of - n, of)),
+        _ => Err(ParseError(s.to_string())),
+    }
+}
+
+/// A fraction that only makes sense as letters read out of a line, e.g. "3/5" (but not "6/6")
+fn letters_of_fraction(s: &str) -> Option<PartialLine> {
+    const MAX_LETTERS_PER_LINE: u8 = 8;
+    let (n, of) = s.split_once('/')?;
+    let (n, of): (u8, u8) = (n.parse().ok()?, of.parse().ok()?);
+    (n < of && of <= MAX_LETTERS_PER_LINE).then_some(PartialLine::Read(n, of))
+}
+
+/// Like `extract_float`, but keeping the sign of a leading number, e.g. "-0.1 logmar" => -0.1
+pub(crate) fn extract_signed_float(s: &str) -> VisualAcuityResult<f64> {
+    let end = s
+        .find(|c: char| !(c.is_numeric() || "+-.".contains(c)))
+        .unwrap_or(s.len());
+    Ok(s[..end].parse()?)
+}
+
 pub(crate) fn extract_floats<T: HomogeneousTuple>(s: &str) -> VisualAcuityResult<T>
 where
     T::Item: FromStr,
@@ -116,6 +242,7 @@ pub(crate) fn handle_error<'a>(
 
 #[cfg(test)]
 mod tests {
+    use crate::charts::Charts;
     use crate::dataquality::DataQuality;
     use crate::parser::decorator::Content;
     use crate::parser::grammar_helpers::merge_consecutive_texts;
@@ -140,11 +267,17 @@ mod tests {
                     data_quality: DataQuality::ConvertibleFuzzy,
                 },
             ],
-            vec![Text("asdf qwerty".to_string())],
+            vec![Content {
+                content: Text("asdf qwerty".to_string()),
+                left: 0,
+                right: 11,
+                input: "asdf qwerty",
+                data_quality: DataQuality::ConvertibleFuzzy,
+            }],
         )];
         for (va, expected) in test_cases {
-            let actual = merge_consecutive_texts(va).content;
-            assert_eq!(actual, expected.into_iter().collect());
+            let actual = merge_consecutive_texts(va, &Charts::default()).content;
+            assert_eq!(actual, expected);
         }
     }
 }
@@ -2,8 +2,9 @@
 // wrappers by the same names for the sake of convenience, shimming in common interfaces for
 // `.new()` and `.parse()`
 
+use crate::charts::{Charts, DEFAULT_CHARTS};
 use crate::parser::Content;
-use crate::{ParsedItemCollection, VisualAcuityResult};
+use crate::{ParsedItem, VisualAcuityResult};
 
 pub(crate) trait Parse<'a, T> {
     fn new() -> Self;
@@ -11,7 +12,8 @@ pub(crate) trait Parse<'a, T> {
 }
 
 macro_rules! impl_parser {
-    ($p:ty, $i:ty, $t:ty) => {
+    // Any extra grammar arguments are passed between `orig` and the lowercased text
+    ($p:ty, $i:ty, $t:ty $(, $arg:expr)*) => {
         impl<'a> Parse<'a, $t> for $p {
             fn new() -> Self {
                 Self(<$i>::new())
@@ -19,7 +21,7 @@ macro_rules! impl_parser {
 
             fn parse(&'a self, orig: &'a str) -> VisualAcuityResult<$t> {
                 let s = orig.to_lowercase();
-                Ok(self.0.parse(orig, s.as_str())?)
+                Ok(self.0.parse(orig, $($arg,)* s.as_str())?)
             }
         }
     };
@@ -27,17 +29,40 @@ macro_rules! impl_parser {
 
 // Wrap these types so we can do trait implementation
 pub(crate) struct ChartNotesParser(crate::parser::grammar::ChartNotesParser);
-impl<'a> Parse<'a, Content<'a, ParsedItemCollection>> for ChartNotesParser {
+impl<'a> Parse<'a, Content<'a, Vec<Content<'a, ParsedItem>>>> for ChartNotesParser {
     fn new() -> Self {
         Self(crate::parser::grammar::ChartNotesParser::new())
     }
 
-    fn parse(&'a self, orig: &'a str) -> VisualAcuityResult<Content<'a, ParsedItemCollection>> {
+    fn parse(
+        &'a self,
+        orig: &'a str,
+    ) -> VisualAcuityResult<Content<'a, Vec<Content<'a, ParsedItem>>>> {
+        self.parse_with_charts(orig, &DEFAULT_CHARTS)
+    }
+}
+
+impl ChartNotesParser {
+    /// Like `parse`, but only accepting values found in the given `Charts`
+    pub(crate) fn parse_with_charts<'a>(
+        &self,
+        orig: &'a str,
+        charts: &Charts,
+    ) -> VisualAcuityResult<Content<'a, Vec<Content<'a, ParsedItem>>>> {
         let s = orig.to_lowercase();
-        let parsed = self.0.parse(orig, s.as_str())?;
+        let parsed = self.0.parse(orig, charts, s.as_str())?;
         // Do a little switcheroo for lifetime reasons:
+        let content = parsed
+            .content
+            .into_iter()
+            .map(|item| Content {
+                input: orig,
+                ..item
+            })
+            .collect();
         Ok(Content {
             input: orig,
+            content,
             ..parsed
         })
     }
@@ -55,7 +80,8 @@ pub(crate) struct PlusLettersParser(crate::parser::grammar::PlusLettersParser);
 impl_parser!(
     PlusLettersParser,
     crate::parser::grammar::PlusLettersParser,
-    crate::ParsedItem
+    crate::ParsedItem,
+    &DEFAULT_CHARTS
 );
 
 #[allow(dead_code)]
@@ -63,7 +89,8 @@ pub(crate) struct JaegerExactParser(crate::parser::grammar::JaegerExactParser);
 impl_parser!(
     JaegerExactParser,
     crate::parser::grammar::JaegerExactParser,
-    crate::ParsedItem
+    crate::ParsedItem,
+    &DEFAULT_CHARTS
 );
 
 #[allow(dead_code)]
@@ -71,7 +98,8 @@ pub(crate) struct SnellenExactParser(crate::parser::grammar::SnellenExactParser)
 impl_parser!(
     SnellenExactParser,
     crate::parser::grammar::SnellenExactParser,
-    crate::ParsedItem
+    crate::ParsedItem,
+    &DEFAULT_CHARTS
 );
 
 #[allow(dead_code)]
@@ -79,5 +107,6 @@ pub(crate) struct DistanceUnitsParser(crate::parser::grammar::DistanceUnitsParse
 impl_parser!(
     DistanceUnitsParser,
     crate::parser::grammar::DistanceUnitsParser,
-    crate::DistanceUnits
+    crate::DistanceUnits,
+    &DEFAULT_CHARTS
 );
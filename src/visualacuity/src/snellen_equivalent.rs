@@ -1,4 +1,6 @@
-use crate::charts::ChartRow;
+use crate::charts::{ChartRow, ChartedItem, DEFAULT_CHARTS};
+use crate::helpers::RoundPlaces;
+use crate::logmar::LogMarScales;
 use crate::VisualAcuityError::*;
 use crate::*;
 
@@ -7,28 +9,50 @@ pub(crate) trait SnellenEquivalent {
 }
 
 impl SnellenEquivalent for ParsedItem {
+    fn snellen_equivalent(&self) -> VisualAcuityResult<Fraction> {
+        self.with_charts(&DEFAULT_CHARTS).snellen_equivalent()
+    }
+}
+
+impl SnellenEquivalent for ChartedItem<'_> {
     fn snellen_equivalent(&self) -> VisualAcuityResult<Fraction> {
         // This leans on data found in the files assets/charts/*.tsv
-        let error = |_| NoSnellenEquivalent(self.to_string());
+        let error = |_| NoSnellenEquivalent(self.item.to_string());
+        if let LogMar(log_mar) = self.item {
+            // Back-compute a 20/x fraction, rounded to the nearest foot
+            let log_mar: f64 = log_mar.parse()?;
+            let row = (20.0 * log_mar.minimum_angle_of_resolution()).round();
+            return Ok(Fraction((20.0, row)));
+        }
+        if let Some((Fraction((distance, row)), _)) = self.find_scaled_row() {
+            // e.g. "10/200" => "20/400"
+            return Ok(Fraction((20.0, (20.0 * row / distance).round())));
+        }
         match self.find_chart_row() {
-            Ok(ChartRow {
-                fraction: Some(ref_acuity),
-                reference_distance,
-                ..
-            }) => {
+            Ok(
+                row @ ChartRow {
+                    fraction: Some(ref_acuity),
+                    reference_distance,
+                    ..
+                },
+            ) => {
                 if reference_distance == &DistanceUnits::NotProvided {
                     // Found a chart row + no conversion necessary.
                     return Ok(ref_acuity.clone());
                 }
                 // Found a chart row + conversion necessary.
-                let feet = self.measurement_distance().to_feet().map_err(error)?;
+                let feet = self.measurement_distance(row).to_feet().map_err(error)?;
                 let ref_feet = reference_distance.to_feet().map_err(error)?;
+                if feet.round_places(2) == ref_feet.round_places(2) {
+                    // Measured at the chart's own distance (e.g. "55 letters @ 4m")
+                    return Ok(*ref_acuity);
+                }
                 let Fraction((converted_distance, ref_row)) = ref_acuity.clone();
                 let converted_row = ref_row * ref_feet / feet;
                 let rounded_row = (converted_row as u64) as f64;
                 Ok(Fraction((converted_distance, rounded_row)))
             }
-            _ => Err(NoSnellenEquivalent(self.to_string())),
+            _ => Err(NoSnellenEquivalent(self.item.to_string())),
         }
     }
 }
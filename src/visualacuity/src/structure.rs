@@ -1,14 +1,16 @@
 use std::fmt::{Debug, Display, Formatter};
 use std::hash::{Hash, Hasher};
-use std::ops::Deref;
+use std::ops::{Deref, Range};
 use std::slice::Iter;
 use std::str::FromStr;
 
 use lazy_static::lazy_static;
 use regex::Regex;
 
-use crate::charts::ChartRow;
+use crate::charts::{ChartRow, ChartedItem, Charts};
 use crate::helpers::RoundPlaces;
+use crate::logmar::{LogMarBase, LogMarPlusLetters};
+use crate::parser::Content;
 use crate::DistanceUnits::NotProvided;
 use crate::VisualAcuityError::*;
 use crate::*;
@@ -81,8 +83,6 @@ pub enum FixationPreference {
     FixAndFollow,
     NoFixAndFollow,
     FixNoFollow,
-    Prefers,
-    Holds,
     Eccentric,
 }
 
@@ -92,6 +92,101 @@ impl Display for FixationPreference {
     }
 }
 
+impl FixationPreference {
+    /// For the CSM family, whether fixation is central ("C") rather than uncentral ("UC")
+    pub fn central(&self) -> Option<bool> {
+        self.central_steady_maintained().map(|(c, _, _)| c)
+    }
+
+    /// For the CSM family, whether fixation is steady ("S") rather than unsteady ("US")
+    pub fn steady(&self) -> Option<bool> {
+        self.central_steady_maintained().map(|(_, s, _)| s)
+    }
+
+    /// For the CSM family, whether fixation is maintained ("M") rather than unmaintained ("UM")
+    pub fn maintained(&self) -> Option<bool> {
+        self.central_steady_maintained().map(|(_, _, m)| m)
+    }
+
+    fn central_steady_maintained(&self) -> Option<(bool, bool, bool)> {
+        use FixationPreference::*;
+        match self {
+            CSM => Some((true, true, true)),
+            CUSM => Some((true, false, true)),
+            CSUM => Some((true, true, false)),
+            CUSUM => Some((true, false, false)),
+            UCSM => Some((false, true, true)),
+            UCUSM => Some((false, false, true)),
+            UCSUM => Some((false, true, false)),
+            UCUSUM => Some((false, false, false)),
+            _ => None,
+        }
+    }
+}
+
+/// Which eye is preferred in a binocular fixation test, e.g. "CSM prefers" or "CSM holds"
+#[derive(Hash, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EyePreference {
+    Prefers,
+    Holds,
+}
+
+impl Display for EyePreference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EyePreference::Prefers => write!(f, "prefers"),
+            EyePreference::Holds => write!(f, "holds"),
+        }
+    }
+}
+
+/// Fixation behavior (e.g. "CSM prefers"), decomposed
+#[derive(Hash, Clone, Debug, PartialEq, Eq)]
+pub struct Fixation {
+    /// e.g. `CSM` or `FixAndFollow`
+    pub preference: FixationPreference,
+    /// Whether fixation is central, for the CSM family
+    pub central: Option<bool>,
+    /// Whether fixation is steady, for the CSM family
+    pub steady: Option<bool>,
+    /// Whether fixation is maintained, for the CSM family
+    pub maintained: Option<bool>,
+    /// The eye preference, i.e. `Prefers` or `Holds`, if noted
+    pub eye_preference: Option<EyePreference>,
+}
+
+impl Fixation {
+    pub(crate) fn new(
+        preference: FixationPreference,
+        eye_preference: Option<EyePreference>,
+    ) -> Self {
+        Self {
+            central: preference.central(),
+            steady: preference.steady(),
+            maintained: preference.maintained(),
+            preference,
+            eye_preference,
+        }
+    }
+}
+
+impl Display for Fixation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use FixationPreference::*;
+        match self.preference {
+            FixAndFollow => write!(f, "fix & follow")?,
+            NoFixAndFollow => write!(f, "no fix & follow")?,
+            FixNoFollow => write!(f, "fix, no follow")?,
+            Eccentric => write!(f, "eccentric fixation")?,
+            ref preference => write!(f, "{preference}")?,
+        }
+        match self.eye_preference {
+            Some(eye_preference) => write!(f, " {eye_preference}"),
+            None => Ok(()),
+        }
+    }
+}
+
 #[derive(Hash, Clone, Debug, PartialEq, Eq)]
 pub enum NotTakenReason {
     NT,
@@ -100,18 +195,159 @@ pub enum NotTakenReason {
     Sleeping,
     Prosthesis,
     SeeMR,
+    PoorCooperation,
+    Patched,
+    Dilated,
+    NoTime,
+}
+
+/// Letters read out of a chart line, instead of plus letters (e.g. "20/40 3/5")
+#[derive(Hash, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PartialLine {
+    /// e.g. "3/5" or "4 of 5", i.e. letters read out of the letters on the line
+    Read(u8, u8),
+    /// e.g. "missed 2" or "x2"
+    Missed(u8),
+}
+
+impl PartialLine {
+    /// The equivalent plus letters, as written down (e.g. "3/5" => -2)
+    pub(crate) fn plus_letters(&self) -> i32 {
+        match *self {
+            PartialLine::Read(read, of) => read as i32 - of as i32,
+            PartialLine::Missed(n) => -(n as i32),
+        }
+    }
+
+    /// Whether the letters written down agree with the number of letters on the chart row, if
+    /// the row is known (e.g. "4/5" on a row with 6 letters doesn't)
+    pub(crate) fn agrees_with(&self, n_letters: Option<u8>) -> bool {
+        match (*self, n_letters) {
+            (PartialLine::Read(_, of), Some(n_letters)) => of == n_letters,
+            _ => true,
+        }
+    }
+}
+
+impl Display for PartialLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PartialLine::Read(read, of) => write!(f, "{read}/{of}"),
+            PartialLine::Missed(n) => write!(f, "missed {n}"),
+        }
+    }
+}
+
+/// Whether an acuity was recorded as a bound rather than a point value (e.g. "<20/400").
+/// Relative to the acuity, not the LogMAR: `LessThan` is worse vision, i.e. a higher LogMAR.
+#[derive(Default, Hash, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Qualifier {
+    #[default]
+    Exact,
+    /// e.g. "<20/400" or "worse than 20/200"
+    LessThan,
+    /// e.g. "better than 20/20", "at least 20/40" or "20/400+"
+    GreaterThan,
+}
+
+impl Display for Qualifier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// Which value to report for a range between two chart lines (e.g. "20/40-20/50")
+#[derive(Default, Hash, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RangeConvention {
+    /// The line with the higher LogMAR
+    #[default]
+    Worse,
+    /// The line with the lower LogMAR
+    Better,
+    /// The mean of the two lines' LogMAR values
+    Midpoint,
+}
+
+impl RangeConvention {
+    /// The item to convert (e.g. to LogMAR) in place of a range. Any other item is returned as-is.
+    pub(crate) fn resolve(
+        &self,
+        item: ParsedItem,
+        charts: &Charts,
+    ) -> VisualAcuityResult<ParsedItem> {
+        let AcuityRange(a, b) = item else {
+            return Ok(item);
+        };
+        let log_mar_a = a.with_charts(charts).log_mar_base()?;
+        let log_mar_b = b.with_charts(charts).log_mar_base()?;
+        let (better, worse) = match log_mar_a <= log_mar_b {
+            true => (a, b),
+            false => (b, a),
+        };
+        match self {
+            Self::Worse => Ok(*worse),
+            Self::Better => Ok(*better),
+            Self::Midpoint => Ok(LogMar(format!("{}", (log_mar_a + log_mar_b) / 2.0))),
+        }
+    }
+
+    /// The LogMAR of an item, with plus letters, in place of a range. For the midpoint, the plus
+    /// letters are counted on both lines before taking the mean.
+    pub(crate) fn log_mar_plus_letters(
+        &self,
+        item: ParsedItem,
+        charts: &Charts,
+        plus_letters: &Vec<i32>,
+    ) -> VisualAcuityResult<f64> {
+        match (self, &item) {
+            (Self::Midpoint, AcuityRange(a, b)) => {
+                let log_mar_a = a.with_charts(charts).log_mar_plus_letters(plus_letters)?;
+                let log_mar_b = b.with_charts(charts).log_mar_plus_letters(plus_letters)?;
+                Ok((log_mar_a + log_mar_b) / 2.0)
+            }
+            _ => self
+                .resolve(item, charts)?
+                .with_charts(charts)
+                .log_mar_plus_letters(plus_letters),
+        }
+    }
+}
+
+/// Pediatric optotypes, each with its own chart
+#[derive(Hash, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Optotype {
+    HOTV,
+    Lea,
+    Allen,
+    Kay,
+    Cardiff,
+}
+
+impl Display for Optotype {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
 }
 
 #[derive(Hash, Clone, Debug, PartialEq, Eq)]
 pub enum ParsedItem {
     SnellenFraction(String),
     Jaeger(String),
-    Teller(String),
-    ETDRS(String),
+    Teller(String, DistanceUnits),
+    ETDRS(String, DistanceUnits),
+    Decimal(String),
+    LogMar(String),
     NearTotalLoss(String, DistanceUnits),
+    NearCard(String, DistanceUnits),
+    OptotypeItem(Optotype, String, DistanceUnits),
+    Keeler(String, DistanceUnits),
     VisualResponse(String),
+    FixationItem(Fixation), // e.g. "CSM prefers"
     CrossReferenceItem(String),
     PlusLettersItem(i32),
+    PartialLineItem(PartialLine),
+    QualifierItem(Qualifier),
+    AcuityRange(Box<ParsedItem>, Box<ParsedItem>), // e.g. "20/40-20/50"
     NotTakenItem(NotTakenReason),
 
     // Visit Info
@@ -129,10 +365,11 @@ impl Display for ParsedItem {
         let formatted = match self {
             SnellenFraction(s)
             | Jaeger(s)
-            | ETDRS(s)
-            | Teller(s)
+            | Decimal(s)
             | VisualResponse(s)
             | CrossReferenceItem(s) => s.to_string(),
+            FixationItem(fixation) => fixation.to_string(),
+            LogMar(s) => format!("logMAR {s}"),
             PlusLettersItem(n) => {
                 if *n > 0 {
                     format!("+{self}")
@@ -140,10 +377,21 @@ impl Display for ParsedItem {
                     format!("{n}")
                 }
             }
-            NearTotalLoss(method, distance) => match distance.to_feet() {
+            NearTotalLoss(method, distance)
+            | Teller(method, distance)
+            | ETDRS(method, distance)
+            | NearCard(method, distance)
+            | Keeler(method, distance) => match distance.to_feet() {
                 Ok(feet) => format!("{method} @ {} feet", feet.round_places(2)),
-                _ => format!("{method}"),
+                _ => method.to_string(),
+            },
+            OptotypeItem(optotype, s, distance) => match distance.to_feet() {
+                Ok(feet) => format!("{optotype} {s} @ {} feet", feet.round_places(2)),
+                _ => format!("{optotype} {s}"),
             },
+            PartialLineItem(p) => format!("{p}"),
+            QualifierItem(q) => format!("{q}"),
+            AcuityRange(a, b) => format!("{a}-{b}"),
             NotTakenItem(reason) => format!("{reason:?}"),
             DistanceItem(d) => format!("{d}"),
             LateralityItem(l) => format!("{l}"),
@@ -156,25 +404,43 @@ impl Display for ParsedItem {
 }
 
 impl ParsedItem {
-    pub(crate) fn find_chart_row(&self) -> VisualAcuityResult<&ChartRow> {
+    pub(crate) fn find_chart_row<'c>(
+        &self,
+        charts: &'c Charts,
+    ) -> VisualAcuityResult<&'c ChartRow> {
         let key = self.chart_row_key()?;
-        match ChartRow::find(&key) {
+        match charts.find_row(&key) {
             Some(chart_row) => Ok(chart_row),
             None => Err(ChartRowNotFound(key)),
         }
     }
 
+    /// Pair this item with the `Charts` to use for conversions (e.g. to LogMAR)
+    pub(crate) fn with_charts<'a>(&'a self, charts: &'a Charts) -> ChartedItem<'a> {
+        ChartedItem { item: self, charts }
+    }
+
     pub(crate) fn chart_row_key(&self) -> VisualAcuityResult<String> {
         match self {
-            SnellenFraction(_) | ETDRS { .. } | Teller(_) | Jaeger(_) => Ok(self.to_string()),
-            NearTotalLoss(s, ..) => Ok(s.to_string()),
+            SnellenFraction(_) | Jaeger(_) | Decimal(_) => Ok(self.to_string()),
+            NearTotalLoss(s, ..)
+            | Teller(s, ..)
+            | ETDRS(s, ..)
+            | NearCard(s, ..)
+            | Keeler(s, ..) => Ok(s.to_string()),
+            OptotypeItem(optotype, s, _) => Ok(format!("{optotype} {s}")),
             _ => Err(NoSnellenEquivalent(self.to_string())),
         }
     }
 
     pub(crate) fn measurement_distance(&self) -> &DistanceUnits {
         match self {
-            NearTotalLoss(_, distance) => distance,
+            NearTotalLoss(_, distance)
+            | Teller(_, distance)
+            | ETDRS(_, distance)
+            | NearCard(_, distance)
+            | OptotypeItem(.., distance)
+            | Keeler(_, distance) => distance,
             _ => &NotProvided,
         }
     }
@@ -200,6 +466,34 @@ impl FromIterator<ParsedItem> for ParsedItemCollection {
     }
 }
 
+/// Which of a `VisitNote`'s input fields an item was parsed from
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TextSource {
+    #[default]
+    Text,
+    TextPlus,
+}
+
+/// A `ParsedItem` along with where it was found in the original notes
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SpannedItem {
+    /// Whether the item came from `VisitNote::text` or `VisitNote::text_plus`
+    pub source: TextSource,
+    /// Byte offsets of the item within its source field
+    pub span: Range<usize>,
+    pub item: ParsedItem,
+}
+
+impl SpannedItem {
+    pub(crate) fn new(source: TextSource, content: &Content<ParsedItem>) -> Self {
+        Self {
+            source,
+            span: content.left..content.right,
+            item: content.content.clone(),
+        }
+    }
+}
+
 #[derive(Default, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum VAFormat {
     #[default]
@@ -208,7 +502,16 @@ pub enum VAFormat {
     Jaeger,
     ETDRS,
     Teller,
+    Decimal,
+    LogMAR,
     NearTotalLoss,
+    NearCard,
+    HOTV,
+    Lea,
+    Allen,
+    Kay,
+    Cardiff,
+    Keeler,
     VisualResponse,
     PinHole,
     Binocular,
@@ -223,8 +526,20 @@ impl From<ParsedItem> for VAFormat {
             Jaeger { .. } => VAFormat::Jaeger,
             Teller { .. } => VAFormat::Teller,
             ETDRS { .. } => VAFormat::ETDRS,
+            Decimal { .. } => VAFormat::Decimal,
+            LogMar(_) => VAFormat::LogMAR,
             NearTotalLoss { .. } => VAFormat::NearTotalLoss,
-            VisualResponse { .. } => VAFormat::VisualResponse,
+            NearCard { .. } => VAFormat::NearCard,
+            OptotypeItem(optotype, ..) => match optotype {
+                Optotype::HOTV => VAFormat::HOTV,
+                Optotype::Lea => VAFormat::Lea,
+                Optotype::Allen => VAFormat::Allen,
+                Optotype::Kay => VAFormat::Kay,
+                Optotype::Cardiff => VAFormat::Cardiff,
+            },
+            Keeler { .. } => VAFormat::Keeler,
+            AcuityRange(a, _) => (*a).into(),
+            VisualResponse { .. } | FixationItem(_) => VAFormat::VisualResponse,
             PinHoleItem(_) => VAFormat::PinHole,
             NotTakenItem(_) => VAFormat::NotTaken,
             CrossReferenceItem(_) => VAFormat::CrossReference,
@@ -1,7 +1,9 @@
 use crate::helpers::RoundPlaces;
 use crate::tests::tsv_reader::TestCaseRow;
+use crate::LowVisionConvention::*;
 use crate::VisualAcuityError::*;
 use crate::*;
+use test_case::test_case;
 
 #[test]
 fn test_cases_conversions() -> VisualAcuityResult<()> {
@@ -107,3 +109,273 @@ fn test_cases_parsing() -> VisualAcuityResult<()> {
     }
     Ok(())
 }
+
+#[test]
+fn test_parser_shared_across_threads() -> VisualAcuityResult<()> {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Parser>();
+
+    let parser = std::sync::Arc::new(Parser::new());
+    let content = include_str!("../../../../testing/test_cases_conversions.tsv");
+    let rows = TestCaseRow::read_file(content).collect::<Vec<_>>();
+    let expected = rows
+        .iter()
+        .map(|row| {
+            let input: VisitInput = [("EHR Entry", row.get("EHR Entry")?)].into();
+            parser.parse_visit(input)
+        })
+        .collect::<Vec<_>>();
+
+    let handles = (0..4)
+        .map(|_| {
+            let parser = parser.clone();
+            let rows = rows.clone();
+            std::thread::spawn(move || {
+                rows.iter()
+                    .map(|row| {
+                        let input: VisitInput = [("EHR Entry", row.get("EHR Entry")?)].into();
+                        parser.parse_visit(input)
+                    })
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for handle in handles {
+        assert_eq!(handle.join().expect("thread panicked"), expected);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_parse_visits() -> VisualAcuityResult<()> {
+    let parser = Parser::new();
+    let content = include_str!("../../../../testing/test_cases_parsing.tsv");
+    let inputs = TestCaseRow::read_file(content)
+        .map(|row| {
+            Ok([
+                ("EHR Entry", row.get("EHR Entry")?),
+                ("EHR Entry Plus", row.get("EHR Entry Plus")?),
+            ]
+            .into())
+        })
+        .collect::<VisualAcuityResult<Vec<VisitInput>>>()?;
+
+    let expected = inputs
+        .iter()
+        .map(|input| Parser::new().parse_visit(input.clone()))
+        .collect::<Vec<_>>();
+    let actual = parser.parse_visits(inputs);
+
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+const PROJECTOR_CHART: &str = "Row\tText\tLetters\tFraction
+0\t20/20\t5\t20/20
+1\t20/35\t5\t20/35
+2\t20/70\t4\t20/70
+";
+
+fn parse_note(parser: &Parser, notes: &str) -> VisitNote {
+    let visit = parser.parse_visit([("Visual Acuity", notes)].into());
+    let (_, note) = visit.unwrap().into_iter().next().unwrap();
+    note.expect("TEST")
+}
+
+fn parse_log_mar(parser: &Parser, notes: &str) -> OptionResult<f64> {
+    parse_note(parser, notes)
+        .log_mar_base_plus_letters
+        .round_places(2)
+}
+
+#[test_case("0.8 -2", OptionResult::Some(0.14))]
+#[test_case("0.9 -2", OptionResult::Err(PlusLettersNotAllowed); "Monoyer row")]
+fn test_decimal_plus_letters(notes: &str, expected: OptionResult<f64>) {
+    assert_eq!(parse_log_mar(&Parser::new(), notes), expected, "{notes}");
+}
+
+#[test]
+fn test_custom_chart() -> VisualAcuityResult<()> {
+    let parser = Parser::builder()
+        .chart("projector", [PROJECTOR_CHART])
+        .build()?;
+    assert_eq!(parse_log_mar(&Parser::new(), "20/35"), OptionResult::None);
+    assert_eq!(parse_log_mar(&parser, "20/35"), OptionResult::Some(0.24));
+    assert_eq!(parse_log_mar(&parser, "20/35 -1"), OptionResult::Some(0.30));
+    // Predefined charts are still available
+    assert_eq!(parse_log_mar(&parser, "20/400"), OptionResult::Some(1.30));
+    Ok(())
+}
+
+#[test]
+fn test_custom_chart_files() -> VisualAcuityResult<()> {
+    let path = std::env::temp_dir().join("visualacuity-test-projector.tsv");
+    std::fs::write(&path, PROJECTOR_CHART).unwrap();
+    let parser = Parser::builder()
+        .chart_files("projector", [&path])
+        .build()?;
+    assert_eq!(parse_log_mar(&parser, "20/35"), OptionResult::Some(0.24));
+
+    let missing = Parser::builder().chart_files("projector", ["not-a-file.tsv"]);
+    assert!(matches!(missing.build(), Err(ChartNotFound(_))));
+    Ok(())
+}
+
+#[test]
+fn test_custom_chart_invalid() {
+    let no_rows = Parser::builder().chart("projector", ["Text\tFraction\n20/35\t20/35"]);
+    assert!(matches!(no_rows.build(), Err(ParseError(_))));
+    let no_fraction = Parser::builder().chart("projector", ["Row\tText\tFraction\n1\t20/35\tx"]);
+    assert!(matches!(no_fraction.build(), Err(ParseError(_))));
+}
+
+#[test]
+fn test_chart_order() -> VisualAcuityResult<()> {
+    // 20/130 only exists on the Teller chart, and 20/100 is on both Snellen & Bailey-Lovie
+    let default = Parser::new();
+    assert_eq!(
+        parse_log_mar(&default, "20/100 -1"),
+        OptionResult::Some(0.75)
+    );
+    assert_eq!(parse_log_mar(&default, "20/130"), OptionResult::Some(0.81));
+
+    let parser = Parser::builder()
+        .chart_order(["bailey-lovie", "snellen"])
+        .build()?;
+    assert_eq!(
+        parse_log_mar(&parser, "20/100 -1"),
+        OptionResult::Some(0.72)
+    );
+    assert_eq!(parse_log_mar(&parser, "20/130"), OptionResult::None);
+
+    let unknown = Parser::builder().chart_order(["snellen", "not-a-chart"]);
+    assert!(matches!(unknown.build(), Err(ChartNotFound(_))));
+    Ok(())
+}
+
+#[test]
+fn test_column_chart_order() -> VisualAcuityResult<()> {
+    let parser = Parser::builder()
+        .column_chart_order("Retina VA Right Eye", ["bailey-lovie"])
+        .build()?;
+    let visit = parser.parse_visit(
+        [
+            ("Visual Acuity Right Eye", "20/100 -1"),
+            ("Retina VA Right Eye", "20/100 -1"),
+        ]
+        .into(),
+    )?;
+    let actual = visit
+        .into_iter()
+        .map(|(key, note)| (key, note.unwrap().log_mar_base_plus_letters.round_places(2)))
+        .collect::<Vec<_>>();
+    let expected = vec![
+        (s!("Retina VA Right Eye"), OptionResult::Some(0.72)),
+        (s!("Visual Acuity Right Eye"), OptionResult::Some(0.75)),
+    ];
+    assert_eq!(actual, expected);
+    Ok(())
+}
+
+#[test_case(None, "20/20", OptionResult::Some(0.0), None)]
+#[test_case(None, "CF 30cm", OptionResult::Some(1.85), Some(SchulzeBonzel))]
+#[test_case(None, "LP", OptionResult::Err(GenericError), Some(SchulzeBonzel))]
+#[test_case(Some(Holladay), "CF 2ft", OptionResult::Some(2.0), Some(Holladay))]
+#[test_case(Some(Holladay), "HM 2ft", OptionResult::Some(3.0), Some(Holladay))]
+#[test_case(Some(Lange), "CF 30cm", OptionResult::Some(1.9), Some(Lange))]
+#[test_case(Some(Lange), "HM", OptionResult::Some(2.3), Some(Lange))]
+#[test_case(Some(Lange), "LP", OptionResult::Some(2.7), Some(Lange))]
+#[test_case(Some(Lange), "NLP", OptionResult::Some(3.0), Some(Lange))]
+#[test_case(None, "HOTV 20/32 @ 10ft", OptionResult::Err(GenericError), None; "no reference distance to scale from")]
+fn test_low_vision_convention(
+    convention: Option<LowVisionConvention>,
+    notes: &str,
+    expected: OptionResult<f64>,
+    expected_convention: Option<LowVisionConvention>,
+) -> VisualAcuityResult<()> {
+    let builder = Parser::builder();
+    let builder = match convention {
+        Some(convention) => builder.low_vision_convention(convention),
+        None => builder,
+    };
+    let note = parse_note(&builder.build()?, notes);
+    let actual = note.log_mar_base.round_places(2).map_err(|_| GenericError);
+    assert_eq!(actual, expected, "{notes}");
+    assert_eq!(note.low_vision_convention, expected_convention, "{notes}");
+    Ok(())
+}
+
+#[test]
+fn test_low_vision_convention_without_low_vision_chart() {
+    let builder = Parser::builder().low_vision_convention(Lange);
+    let no_chart = builder.clone().chart_order(["snellen", "jaeger"]);
+    assert!(matches!(no_chart.build(), Err(ChartNotFound(_))));
+    let no_column_chart = builder.clone().column_chart_order("Retina VA", ["snellen"]);
+    assert!(no_column_chart.build().is_ok());
+    let only_column_chart = builder
+        .chart_order(["snellen"])
+        .column_chart_order("Visual Acuity", ["snellen", "lange"]);
+    assert!(only_column_chart.build().is_ok());
+}
+
+#[test_case(RangeConvention::Worse, "20/40-20/50", OptionResult::Some(0.4), s!("20/50"))]
+#[test_case(RangeConvention::Worse, "20/50 to 20/40", OptionResult::Some(0.4), s!("20/50"))]
+#[test_case(RangeConvention::Better, "20/40-20/50", OptionResult::Some(0.3), s!("20/40"))]
+#[test_case(RangeConvention::Midpoint, "20/40 - 20/50", OptionResult::Some(0.35), s!("20/45"))]
+fn test_range_convention(
+    convention: RangeConvention,
+    notes: &str,
+    expected: OptionResult<f64>,
+    expected_snellen: String,
+) -> VisualAcuityResult<()> {
+    let parser = Parser::builder().range_convention(convention).build()?;
+    let note = parse_note(&parser, notes);
+    assert_eq!(note.log_mar_base.round_places(2), expected, "{notes}");
+    assert_eq!(note.data_quality, DataQuality::Range, "{notes}");
+    assert_eq!(note.va_format, Ok(VAFormat::Snellen), "{notes}");
+    let snellen = note.snellen_equivalent.map(|f| f.to_string());
+    assert_eq!(snellen, OptionResult::Some(expected_snellen), "{notes}");
+    Ok(())
+}
+
+#[test_case(
+    RangeConvention::Worse,
+    "20/40 to 20/50 -1",
+    "",
+    OptionResult::Some(0.41)
+)]
+#[test_case(
+    RangeConvention::Better,
+    "20/40 to 20/50 -1",
+    "",
+    OptionResult::Some(0.32)
+)]
+#[test_case(
+    RangeConvention::Midpoint,
+    "20/40 to 20/50 -1",
+    "",
+    OptionResult::Some(0.37)
+)]
+#[test_case(
+    RangeConvention::Midpoint,
+    "20/40-20/50",
+    "-1",
+    OptionResult::Some(0.37)
+)]
+fn test_range_convention_plus_letters(
+    convention: RangeConvention,
+    notes: &str,
+    plus: &str,
+    expected: OptionResult<f64>,
+) -> VisualAcuityResult<()> {
+    let parser = Parser::builder().range_convention(convention).build()?;
+    let visit = parser.parse_visit([("Visual Acuity", notes), ("Visual Acuity Plus", plus)].into());
+    let (_, note) = visit?.into_iter().next().unwrap();
+    let actual = note
+        .expect("TEST")
+        .log_mar_base_plus_letters
+        .round_places(2);
+    assert_eq!(actual, expected, "{notes} {plus}");
+    Ok(())
+}
@@ -9,6 +9,8 @@ use crate::visitinput::*;
 use crate::Correction::*;
 use crate::DistanceOfMeasurement::*;
 use crate::DistanceUnits::*;
+use crate::EyePreference::*;
+use crate::FixationPreference::*;
 use crate::Laterality::*;
 use crate::VisualAcuityError::*;
 use crate::*;
@@ -29,11 +31,16 @@ fn parse_notes(notes: &str) -> VisualAcuityResult<Vec<ParsedItem>> {
     let notes = notes.trim();
 
     match CHART_NOTES_PARSER.parse(notes) {
-        Ok(Content { content: p, .. }) => Ok(p.into_iter().collect()),
+        Ok(Content { content: p, .. }) => Ok(p.into_iter().map(|item| item.content).collect()),
         Err(e) => Err(ParseError(format!("{e:?}: {notes}"))),
     }
 }
 
+/// e.g. `fixation(CSM, Some(Prefers))` for "CSM prefers"
+fn fixation(preference: FixationPreference, eye_preference: Option<EyePreference>) -> ParsedItem {
+    FixationItem(Fixation::new(preference, eye_preference))
+}
+
 #[test_case("EHR Entry", Ok(EntryMetadata::default()))]
 #[test_case("CC VA", Ok(EntryMetadata{correction: CC, ..EntryMetadata::default() }))]
 fn test_parse_key(
@@ -50,8 +57,8 @@ fn test_parse_key(
 fn test_teller() {
     let expected = vec![
         SnellenFraction(s!("20/23")),
-        Teller(s!("38 cy/cm")),
-        Teller(s!("Card 17")),
+        Teller(s!("38 cy/cm"), NotProvided),
+        Teller(s!("Card 17"), NotProvided),
     ]
     .into_iter()
     .collect();
@@ -166,11 +173,56 @@ fn test_plus_letters(chart_note: &str, expected: Result<ParsedItem, ()>) {
 #[test_case("20/321", Ok(vec ! [
     Text("20/321".to_string()),
 ]); "Make sure 20/321 doesn't get scooped up by 20/32")]
+#[test_case("120/80", Ok(vec![Text(s!("120/80"))]); "not a test distance")]
+#[test_case("200/20", Ok(vec![Text(s!("200/20"))]); "upside down")]
+#[test_case("10/80", Ok(vec![SnellenFraction(s!("10/80"))]); "test distance of 10 feet")]
+#[test_case("10/16", Ok(vec![SnellenFraction(s!("10/16"))]); "near the top of the chart")]
 fn test_fractions_with_plus_letters(chart_note: &str, expected: Result<Vec<ParsedItem>, ()>) {
     let expected = expected.map(|e| e.into_iter().collect());
     assert_eq!(parse_notes(chart_note).map_err(|_| ()), expected);
 }
 
+#[test_case("20/40 3/5", Ok(vec![SnellenFraction(s!("20/40")), PartialLineItem(PartialLine::Read(3, 5))]))]
+#[test_case("20/40 4 of 5", Ok(vec![SnellenFraction(s!("20/40")), PartialLineItem(PartialLine::Read(4, 5))]))]
+#[test_case("20/40 (-2 of 5)", Ok(vec![SnellenFraction(s!("20/40")), PartialLineItem(PartialLine::Read(3, 5))]))]
+#[test_case("20/50 missed 2", Ok(vec![SnellenFraction(s!("20/50")), PartialLineItem(PartialLine::Missed(2))]))]
+#[test_case("20/40 x2", Ok(vec![SnellenFraction(s!("20/40")), PartialLineItem(PartialLine::Missed(2))]))]
+#[test_case("20/40 x 3 attempts", Ok(vec![SnellenFraction(s!("20/40")), Text(s!("x 3 attempts"))]); "times, not missed letters")]
+#[test_case("CF x 3 ft", Ok(vec![NearTotalLoss(s!("CF"), NotProvided), Text(s!("x 3 ft"))]); "times after CF")]
+#[test_case("CF x3", Ok(vec![NearTotalLoss(s!("CF"), NotProvided), Text(s!("x3"))]); "missed letters without a fraction")]
+#[test_case("20/20 6/6", Ok(vec![SnellenFraction(s!("20/20")), SnellenFraction(s!("6/6"))]); "not a partial line")]
+fn test_partial_lines(chart_note: &str, expected: VisualAcuityResult<Vec<ParsedItem>>) {
+    assert_eq!(parse_notes(chart_note), expected, "{chart_note}");
+}
+
+#[test_case("<20/400", Ok(vec![QualifierItem(Qualifier::LessThan), SnellenFraction(s!("20/400"))]))]
+#[test_case("worse than 20/200", Ok(vec![QualifierItem(Qualifier::LessThan), SnellenFraction(s!("20/200"))]))]
+#[test_case("> 20/15", Ok(vec![QualifierItem(Qualifier::GreaterThan), SnellenFraction(s!("20/15"))]))]
+#[test_case("better than 20/20", Ok(vec![QualifierItem(Qualifier::GreaterThan), SnellenFraction(s!("20/20"))]))]
+#[test_case("at least 20/40", Ok(vec![QualifierItem(Qualifier::GreaterThan), SnellenFraction(s!("20/40"))]))]
+#[test_case("20/400+", Ok(vec![SnellenFraction(s!("20/400")), QualifierItem(Qualifier::GreaterThan)]))]
+#[test_case("20/40 +2", Ok(vec![SnellenFraction(s!("20/40")), PlusLettersItem(2)]); "plus letters")]
+fn test_qualifiers(chart_note: &str, expected: VisualAcuityResult<Vec<ParsedItem>>) {
+    assert_eq!(parse_notes(chart_note), expected, "{chart_note}");
+}
+
+#[test_case("20/40-20/50", Ok(vec![AcuityRange(
+    Box::new(SnellenFraction(s!("20/40"))),
+    Box::new(SnellenFraction(s!("20/50"))),
+)]))]
+#[test_case("20/40 to 20/50 -1", Ok(vec![
+    AcuityRange(Box::new(SnellenFraction(s!("20/40"))), Box::new(SnellenFraction(s!("20/50")))),
+    PlusLettersItem(-1),
+]))]
+#[test_case("6/9.5 - 6/12", Ok(vec![AcuityRange(
+    Box::new(SnellenFraction(s!("6/9.5"))),
+    Box::new(SnellenFraction(s!("6/12"))),
+)]))]
+#[test_case("20/40-20/55", Ok(vec![Text(s!("20/40-20/55"))]); "not on the chart")]
+fn test_ranges(chart_note: &str, expected: VisualAcuityResult<Vec<ParsedItem>>) {
+    assert_eq!(parse_notes(chart_note), expected, "{chart_note}");
+}
+
 #[test_case("J1", Ok(vec![Jaeger(s!("J1"))]))]
 #[test_case("J1+", Ok(vec![Jaeger(s!("J1+"))]); "J1plus")]
 #[test_case("J29", Ok(vec![Jaeger(s!("J29"))]))]
@@ -187,6 +239,10 @@ fn test_jaeger(chart_note: &str, expected: VisualAcuityResult<Vec<ParsedItem>>)
 #[test_case("BTL", Ok(vec![VisualResponse(s!("BTL"))]))]
 #[test_case("blink to light", Ok(vec![VisualResponse(s!("BTL"))]))]
 #[test_case("NI", Ok(vec![CrossReferenceItem(s!("NI"))]))]
+#[test_case("no improvement", Ok(vec![CrossReferenceItem(s!("NI"))]))]
+#[test_case("same", Ok(vec![CrossReferenceItem(s!("same"))]))]
+#[test_case("see above", Ok(vec![CrossReferenceItem(s!("see above"))]))]
+#[test_case("Ditto", Ok(vec![CrossReferenceItem(s!("ditto"))]))]
 #[test_case("CF at 1.5ft", Ok(vec![NearTotalLoss(s!("CF"), Feet(1.5) )]))]
 #[test_case("CF 2'", Ok(vec![NearTotalLoss(s!("CF"), Feet(2.0) )]))]
 #[test_case("CF@3'", Ok(vec![NearTotalLoss(s!("CF"), Feet(3.0) )]))]
@@ -197,8 +253,8 @@ fn test_jaeger(chart_note: &str, expected: VisualAcuityResult<Vec<ParsedItem>>)
 #[test_case("CF @ 0.3 meters", Ok(vec![NearTotalLoss(s!("CF"), Meters(0.3) )]))]
 #[test_case("CF @ 30 cm", Ok(vec![NearTotalLoss(s!("CF"), Centimeters(30.0) )]))]
 #[test_case("No BTL", Ok(vec![VisualResponse(s!("no BTL"))]))]
-#[test_case("CSM", Ok(vec![VisualResponse(s!("CSM"))]))]
-#[test_case("CSM-pref", Ok(vec![VisualResponse(s!("CSM")), Text(s!("-pref"))]))]
+#[test_case("CSM", Ok(vec![fixation(CSM, None)]))]
+#[test_case("CSM-pref", Ok(vec![fixation(CSM, None), Text(s!("-pref"))]))]
 fn test_alternative_visual_acuity(chart_note: &str, expected: Result<Vec<ParsedItem>, ()>) {
     let expected = expected.map(|e| e.into_iter().collect());
     assert_eq!(
@@ -208,6 +264,74 @@ fn test_alternative_visual_acuity(chart_note: &str, expected: Result<Vec<ParsedI
     );
 }
 
+#[test_case("0.5", Ok(vec![Decimal(s!("0.5"))]))]
+#[test_case(".5", Ok(vec![Decimal(s!("0.5"))]); "leading point")]
+#[test_case("0,8", Ok(vec![Decimal(s!("0.8"))]); "decimal comma")]
+#[test_case("1.0", Ok(vec![Decimal(s!("1.0"))]))]
+#[test_case("1.25 -2", Ok(vec![Decimal(s!("1.25")), PlusLettersItem(-2)]))]
+#[test_case("0.77", Ok(vec![Text(s!("0.77"))]); "not on the chart")]
+fn test_decimal(chart_note: &str, expected: VisualAcuityResult<Vec<ParsedItem>>) {
+    let actual = parse_notes(chart_note);
+    assert_eq!(actual, expected, "{chart_note}");
+}
+
+#[test_case("logMAR 0.30", Ok(vec![LogMar(s!("0.30"))]))]
+#[test_case("LM 0.1", Ok(vec![LogMar(s!("0.10"))]))]
+#[test_case("log mar 1", Ok(vec![LogMar(s!("1.00"))]))]
+#[test_case("+0.24 logmar", Ok(vec![LogMar(s!("0.24"))]))]
+#[test_case("logmar -0.1", Ok(vec![LogMar(s!("-0.10"))]); "negative")]
+#[test_case("-0.1 logmar", Ok(vec![LogMar(s!("-0.10"))]); "negative suffix")]
+#[test_case("-0.0lm", Ok(vec![LogMar(s!("0.00"))]); "negative zero")]
+#[test_case("logmar 0.125", Ok(vec![LogMar(s!("0.125"))]); "more precision")]
+#[test_case("-0.1", Ok(vec![Text(s!("-0.1"))]); "sign without keyword")]
+fn test_log_mar(chart_note: &str, expected: VisualAcuityResult<Vec<ParsedItem>>) {
+    let actual = parse_notes(chart_note);
+    assert_eq!(actual, expected, "{chart_note}");
+}
+
+#[test_case("N5", Ok(vec![NearCard(s!("N5"), NotProvided)]))]
+#[test_case("N8 @ 40cm", Ok(vec![NearCard(s!("N8"), Centimeters(40.0))]))]
+#[test_case("1.0 M-units", Ok(vec![NearCard(s!("1.0M"), NotProvided)]))]
+#[test_case(".8M at 40 cm", Ok(vec![NearCard(s!("0.8M"), Centimeters(40.0))]))]
+#[test_case("1.0M", Ok(vec![Text(s!("1.0M"))]); "meters without context")]
+#[test_case("N8 0.4m", Ok(vec![NearCard(s!("N8"), Meters(0.4))]); "N-point at decimal meters")]
+#[test_case("N7", Ok(vec![Text(s!("N7"))]); "not on the chart")]
+#[test_case("CF @ 2M", Ok(vec![NearTotalLoss(s!("CF"), Meters(2.0))]); "meters, not M-units")]
+#[test_case("CF @ 0.3m", Ok(vec![NearTotalLoss(s!("CF"), Meters(0.3))]); "decimal meters, not M-units")]
+#[test_case("CF @ 0.5m", Ok(vec![NearTotalLoss(s!("CF"), Meters(0.5))]); "decimal meters on the chart")]
+#[test_case("HM @ 1.5m", Ok(vec![NearTotalLoss(s!("HM"), Meters(1.5))]); "decimal meters after HM")]
+#[test_case("CF 0.5m", Ok(vec![NearTotalLoss(s!("CF"), Meters(0.5))]); "decimal meters without at")]
+#[test_case("CF 1.5m", Ok(vec![NearTotalLoss(s!("CF"), Meters(1.5))]); "decimal meters off the chart")]
+fn test_near_card(chart_note: &str, expected: VisualAcuityResult<Vec<ParsedItem>>) {
+    let actual = parse_notes(chart_note);
+    assert_eq!(actual, expected, "{chart_note}");
+}
+
+#[test_case("HOTV 20/32", Ok(vec![OptotypeItem(Optotype::HOTV, s!("20/32"), NotProvided)]))]
+#[test_case("Lea 10/16", Ok(vec![OptotypeItem(Optotype::Lea, s!("10/16"), NotProvided)]))]
+#[test_case("LEA symbols 10/20 -1", Ok(vec![
+    OptotypeItem(Optotype::Lea, s!("10/20"), NotProvided),
+    PlusLettersItem(-1),
+]))]
+#[test_case("Allen 20/30 @ 10ft", Ok(vec![OptotypeItem(Optotype::Allen, s!("20/30"), Feet(10.0))]))]
+#[test_case("Kay 6/9", Ok(vec![OptotypeItem(Optotype::Kay, s!("6/9"), NotProvided)]))]
+#[test_case("Kay 20/30", Ok(vec![Text(s!("Kay 20/30"))]); "not on the chart")]
+fn test_optotype(chart_note: &str, expected: VisualAcuityResult<Vec<ParsedItem>>) {
+    let actual = parse_notes(chart_note);
+    assert_eq!(actual, expected, "{chart_note}");
+}
+
+#[test_case("Cardiff 6/24 @ 50cm", Ok(vec![OptotypeItem(Optotype::Cardiff, s!("6/24"), Centimeters(50.0))]))]
+#[test_case("Cardiff cards 6/12", Ok(vec![OptotypeItem(Optotype::Cardiff, s!("6/12"), NotProvided)]))]
+#[test_case("4.8 cpd", Ok(vec![Keeler(s!("4.8 cpd"), NotProvided)]))]
+#[test_case("Keeler 13 cy/deg @ 55cm", Ok(vec![Keeler(s!("13 cpd"), Centimeters(55.0))]))]
+#[test_case("19 cy/cm", Ok(vec![Teller(s!("19 cy/cm"), NotProvided)]); "still Teller")]
+#[test_case("5 cpd", Ok(vec![Text(s!("5 cpd"))]); "not on the chart")]
+fn test_grating_cards(chart_note: &str, expected: VisualAcuityResult<Vec<ParsedItem>>) {
+    let actual = parse_notes(chart_note);
+    assert_eq!(actual, expected, "{chart_note}");
+}
+
 #[test]
 fn test_distance_conversion() {
     assert_almost_eq!(Centimeters(30.0).to_feet(), Ok(0.9843), 4);
@@ -241,7 +365,7 @@ Text("ok".to_string()),
     Text("asdf qwerty".to_string()),
 ]))]
 #[test_case("12/20 +1 -3", Ok(vec ! [
-    Text("12/20".to_string()),
+    SnellenFraction(s!("12/20")),
     PlusLettersItem(1),
     PlusLettersItem(- 3),
 ]))]
@@ -258,61 +382,74 @@ fn test_plain_text_notes(chart_note: &'static str, expected: VisualAcuityResult<
 ]))]
 #[test_case("20/23 (38.0 cy/cm) Card 17", Ok(vec ! [
     SnellenFraction(s!("20/23")),
-    Teller(s!("38 cy/cm")),
-    Teller(s!("Card 17")),
+    Teller(s!("38 cy/cm"), NotProvided),
+    Teller(s!("Card 17"), NotProvided),
 ]))]
 #[test_case("20/130 (6.5 cy/cm) Card 12", Ok(vec ! [
     SnellenFraction(s!("20/130")),
-    Teller(s!("6.5 cy/cm")),
-    Teller(s!("Card 12")),
+    Teller(s!("6.5 cy/cm"), NotProvided),
+    Teller(s!("Card 12"), NotProvided),
+]))]
+#[test_case("card 12 @ 55cm", Ok(vec ! [
+    Teller(s!("Card 12"), Centimeters(55.0)),
+]))]
+#[test_case("6.5 cy/cm at 84 cm", Ok(vec ! [
+    Teller(s!("6.5 cy/cm"), Centimeters(84.0)),
 ]))]
 fn test_whole_thing(chart_note: &'static str, expected: Result<Vec<ParsedItem>, ()>) {
     assert_eq!(parse_notes(chart_note).map_err(|_| ()), expected);
 }
 
 #[test_case("fix and follow", Ok(vec ! [
-    VisualResponse(s!("fix & follow"))
+    fixation(FixAndFollow, None)
 ]))]
 #[test_case("fix & follow", Ok(vec ! [
-    VisualResponse(s!("fix & follow"))
+    fixation(FixAndFollow, None)
 ]))]
 #[test_case("FF", Ok(vec ! [
-    VisualResponse(s!("fix & follow")),
+    fixation(FixAndFollow, None),
 ]))]
 #[test_case("F + F", Ok(vec ! [
-    VisualResponse(s!("fix & follow")),
+    fixation(FixAndFollow, None),
 ]); "F + F with plus sign and spaces")]
 #[test_case("F+F", Ok(vec ! [
-    VisualResponse(s!("fix & follow")),
+    fixation(FixAndFollow, None),
 ]); "F + F with plus sign no spaces")]
 #[test_case("F&F", Ok(vec ! [
-    VisualResponse(s!("fix & follow")),
+    fixation(FixAndFollow, None),
 ]); "F + F with ampersand no spaces")]
 #[test_case("f/f", Ok(vec ! [
-    VisualResponse(s!("fix & follow")),
+    fixation(FixAndFollow, None),
 ]); "F + F with slash no spaces")]
 #[test_case("no fix & follow", Ok(vec ! [
-    VisualResponse(s!("no fix & follow")),
+    fixation(NoFixAndFollow, None),
 ]))]
 #[test_case("Fix, No Follow", Ok(vec ! [
-    VisualResponse(s!("fix, no follow")),
+    fixation(FixNoFollow, None),
 ]))]
 #[test_case("CSM, good f+f", Ok(vec ! [
-    VisualResponse(s!("CSM")),
+    fixation(CSM, None),
     Text("good".to_string()),
-    VisualResponse(s!("fix & follow")),
+    fixation(FixAndFollow, None),
 ]))]
 fn test_fix_and_follow(chart_note: &'static str, expected: VisualAcuityResult<Vec<ParsedItem>>) {
     assert_eq!(parse_notes(chart_note), expected, "{chart_note}");
 }
 
-#[test_case("CSM Pref", Ok(vec ! [VisualResponse(s!("CSM prefers"))]))]
+#[test_case("CSM Pref", Ok(vec ! [fixation(CSM, Some(Prefers))]))]
+#[test_case("CUSM holds", Ok(vec![fixation(CUSM, Some(Holds))]))]
+#[test_case("eccentric fixation", Ok(vec![fixation(Eccentric, None)]))]
+#[test_case("Ecc. fix", Ok(vec![fixation(Eccentric, None)]))]
+#[test_case("ecc", Ok(vec![Text(s!("ecc"))]))]
 #[test_case("j1", Ok(vec![Jaeger(s!("J1"))]))]
 #[test_case("j30", Ok(vec ! [Text("j30".to_string())]))]
-#[test_case("79 letters", Ok(vec![ETDRS(s!("79 letters"))]))]
-#[test_case("81ltrs", Ok(vec![ETDRS(s!("81 letters"))]))]
-#[test_case("20/13 ETDRS (95 letters)", Ok(vec![Text("20/13".to_string()), ETDRS(s!("95 letters"))]))]
-#[test_case("20/20 ETDRS (83 letters)", Ok(vec![SnellenFraction(s!("20/20")), ETDRS(s!("83 letters"))]))]
+#[test_case("79 letters", Ok(vec![ETDRS(s!("79 letters"), NotProvided)]))]
+#[test_case("81ltrs", Ok(vec![ETDRS(s!("81 letters"), NotProvided)]))]
+#[test_case("20/13 ETDRS (95 letters)", Ok(vec![Text("20/13".to_string()), ETDRS(s!("95 letters"), NotProvided)]))]
+#[test_case("20/20 ETDRS (83 letters)", Ok(vec![SnellenFraction(s!("20/20")), ETDRS(s!("83 letters"), NotProvided)]))]
+#[test_case("55 letters @ 2m", Ok(vec![ETDRS(s!("55 letters"), Meters(2.0))]))]
+#[test_case("12 letters @ 4m + 25 letters @ 1m", Ok(vec![ETDRS(s!("37 letters"), NotProvided)]))]
+#[test_case("ETDRS 45 @ 4m + 20 @ 1m", Ok(vec![ETDRS(s!("75 letters"), NotProvided)]))]
 fn test_various(chart_note: &'static str, expected: VisualAcuityResult<Vec<ParsedItem>>) {
     assert_eq!(parse_notes(chart_note), expected, "{chart_note}");
 }
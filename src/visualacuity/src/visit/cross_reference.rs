@@ -0,0 +1,101 @@
+use itertools::Itertools;
+
+use crate::visit::{PartialVisit, VisitNote};
+use crate::DataQuality::*;
+use crate::PinHole;
+
+impl PartialVisit {
+    /// Fill in each cross-reference (e.g. "NI" in a pinhole field) with the values of the field it
+    /// refers to, e.g. the same eye's acuity without pinhole. References that could point to more
+    /// than one field are left as they are.
+    pub(crate) fn resolve_cross_references(self) -> Self {
+        let fields = self
+            .0
+            .iter()
+            .filter_map(|(key, note)| match note {
+                Ok(Some(note)) => Some((key, note)),
+                _ => None,
+            })
+            .collect_vec();
+        let targets = fields
+            .iter()
+            .filter(|(_, note)| {
+                !matches!(
+                    note.data_quality,
+                    NoValue | CrossReference | Multiple | Observations
+                )
+            })
+            .copied()
+            .collect_vec();
+        let resolved = fields
+            .iter()
+            .filter(|(_, note)| note.data_quality == CrossReference)
+            .filter_map(|&(key, note)| {
+                let (target_key, target) = referenced_field(note, &targets)?;
+                Some((key.clone(), note.clone().inherit(target_key, target)))
+            })
+            .collect_vec();
+
+        let mut visit = self;
+        for (key, note) in resolved {
+            visit.0.insert(key, Ok(Some(note)));
+        }
+        visit
+    }
+}
+
+/// The field a cross-reference points to: the one sharing the most metadata with it (e.g. the
+/// other eye, for "same"), or for "NI", the same eye without pinhole.
+fn referenced_field<'a>(
+    note: &VisitNote,
+    targets: &[(&'a String, &'a VisitNote)],
+) -> Option<(&'a String, &'a VisitNote)> {
+    let no_improvement = note.extracted_value == "NI";
+    let shared_metadata = |target: &VisitNote| {
+        [
+            target.laterality == note.laterality,
+            target.distance_of_measurement == note.distance_of_measurement,
+            target.correction == note.correction,
+            target.pinhole == note.pinhole,
+        ]
+        .into_iter()
+        .filter(|&shared| shared)
+        .count()
+    };
+    targets
+        .iter()
+        .filter(|(_, target)| {
+            !no_improvement
+                || (target.laterality == note.laterality && target.pinhole != PinHole::With)
+        })
+        .max_set_by_key(|(_, target)| shared_metadata(target))
+        .into_iter()
+        .exactly_one()
+        .ok()
+        .copied()
+}
+
+impl VisitNote {
+    /// Take the acuity values of the note in field `key`, keeping this note's own metadata
+    fn inherit(self, key: &str, from: &VisitNote) -> Self {
+        let from = from.clone();
+        Self {
+            va_format: from.va_format,
+            plus_letters: from.plus_letters,
+            extracted_value: from.extracted_value,
+            snellen_equivalent: from.snellen_equivalent,
+            log_mar_base: from.log_mar_base,
+            log_mar_base_plus_letters: from.log_mar_base_plus_letters,
+            decimal_acuity: from.decimal_acuity,
+            minimum_angle_of_resolution: from.minimum_angle_of_resolution,
+            visual_acuity_score: from.visual_acuity_score,
+            low_vision_convention: from.low_vision_convention,
+            optotype: from.optotype,
+            qualifier: from.qualifier,
+            fixation: from.fixation,
+            not_taken_reason: from.not_taken_reason,
+            inherited_from: Some(key.to_string()),
+            ..self
+        }
+    }
+}
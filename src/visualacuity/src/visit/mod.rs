@@ -1,8 +1,9 @@
 use std::collections::BTreeMap;
 
+use crate::charts::{Charts, LowVisionConvention};
 use crate::dataquality::DataQuality;
 use crate::errors::OptionResult;
-use crate::logmar::{LogMarBase, LogMarPlusLetters};
+use crate::logmar::LogMarScales;
 use crate::parser::Content;
 use crate::snellen_equivalent::SnellenEquivalent;
 use crate::structure::{Fraction, VAFormat};
@@ -11,10 +12,12 @@ use crate::DataQuality::*;
 use crate::VisualAcuityError::MultipleValues;
 use crate::*;
 use crate::{CorrectionItem, ParsedItem, ParsedItemCollection, VisualAcuityResult};
+use crate::{SpannedItem, TextSource};
 use itertools::Itertools;
 use metadata::DistanceOfMeasurement::Distance;
 use metadata::{Correction, DistanceOfMeasurement, Laterality, PinHole};
 
+mod cross_reference;
 pub(crate) mod metadata;
 mod tests;
 
@@ -58,6 +61,36 @@ pub struct Visit(pub(crate) BTreeMap<String, Option<VisitNote>>);
 
 impl_into_iter!(Visit, BTreeMap<String, Option<VisitNote>>);
 
+/// Like `Visit`, but with a separate result for each EHR field, so that a field that fails to
+/// parse doesn't discard the fields that succeeded
+#[derive(PartialEq, Debug, Clone)]
+pub struct PartialVisit(pub(crate) BTreeMap<String, VisualAcuityResult<Option<VisitNote>>>);
+
+impl_into_iter!(
+    PartialVisit,
+    BTreeMap<String, VisualAcuityResult<Option<VisitNote>>>
+);
+
+impl TryFrom<PartialVisit> for Visit {
+    type Error = VisualAcuityError;
+
+    /// Succeeds only if every field was parsed successfully
+    fn try_from(partial_visit: PartialVisit) -> VisualAcuityResult<Self> {
+        use VisualAcuityError::*;
+
+        let (parsed_visit_notes, errors): (_, Vec<_>) = partial_visit
+            .into_iter()
+            .map(|(key, visit_note)| Ok((key, visit_note?)))
+            .partition_result();
+
+        if !errors.is_empty() {
+            return Err(MultipleErrors(errors));
+        }
+
+        Ok(Visit(parsed_visit_notes))
+    }
+}
+
 /// The parsed & processed observations from an EHR field (with "plus" columns merged if possible)
 #[derive(PartialEq, Debug, Clone)]
 pub struct VisitNote {
@@ -91,6 +124,34 @@ pub struct VisitNote {
     pub log_mar_base: OptionResult<f64>,
     /// The LogMAR equivalent of the visual acuity (if available), with consideration of partial lines
     pub log_mar_base_plus_letters: OptionResult<f64>,
+    /// The decimal acuity (if available), e.g. 0.5 for 20/40, with consideration of partial lines
+    pub decimal_acuity: OptionResult<f64>,
+    /// The minimum angle of resolution in arcminutes (if available), with consideration of partial lines
+    pub minimum_angle_of_resolution: OptionResult<f64>,
+    /// The Visual Acuity Score, 100 - 50 * LogMAR (if available), with consideration of partial lines
+    pub visual_acuity_score: OptionResult<f64>,
+
+    /// For near-total vision loss (CF/HM/LP/NLP), the convention used to convert it to LogMAR
+    pub low_vision_convention: Option<LowVisionConvention>,
+
+    /// For pediatric optotypes (e.g. HOTV or Lea symbols), which one was used
+    pub optotype: Option<Optotype>,
+    /// Whether the acuity is a bound (e.g. "<20/400") rather than a point value
+    pub qualifier: Qualifier,
+    /// For fixation behavior (e.g. "CSM" or "fix & follow"), its components
+    pub fixation: Option<Fixation>,
+    /// When the acuity wasn't measured (e.g. "refused" or "patched"), why not
+    pub not_taken_reason: Option<NotTakenReason>,
+
+    /// Every item found in `text` and `text_plus`, with its location, including ignored text
+    pub parsed_items: Vec<SpannedItem>,
+
+    /// When the text holds several observations (e.g. "OD 20/40 OS 20/30" or "20/40 PH 20/25"),
+    /// one note for each, with its own metadata. Otherwise empty.
+    pub observations: Vec<VisitNote>,
+
+    /// For a cross-reference (e.g. "NI" or "same"), the EHR field its values were filled in from
+    pub inherited_from: Option<String>,
 }
 
 impl VisitNote {
@@ -98,32 +159,119 @@ impl VisitNote {
     /// acuity measurements etc. The task here mostly has to do with prioritization/disambiguation.
     pub(crate) fn new(
         entry_metadata: EntryMetadata,
-        parsed_text: Content<ParsedItemCollection>,
-        parsed_text_plus: Content<ParsedItemCollection>,
+        parsed_text: Content<Vec<Content<ParsedItem>>>,
+        parsed_text_plus: Content<Vec<Content<ParsedItem>>>,
+        charts: &Charts,
+        range_convention: RangeConvention,
+    ) -> VisualAcuityResult<Self> {
+        let data_quality = parsed_text.data_quality.max(parsed_text_plus.data_quality);
+        let texts = (parsed_text.input, parsed_text_plus.input);
+        let sourced_items = [
+            (TextSource::Text, parsed_text.content),
+            (TextSource::TextPlus, parsed_text_plus.content),
+        ]
+        .into_iter()
+        .flat_map(|(source, items)| items.into_iter().map(move |item| (source, item)))
+        .collect_vec();
+
+        let new_note = |metadata: EntryMetadata, data_quality, items: &[SourcedItem]| {
+            let parsed_items = items
+                .iter()
+                .map(|(source, item)| SpannedItem::new(*source, item))
+                .collect();
+            Self::from_items(
+                metadata,
+                texts,
+                data_quality,
+                parsed_items,
+                charts,
+                range_convention,
+            )
+        };
+        // Metadata in the text (e.g. "OS 20/30") overrides the metadata from the EHR field name
+        let with_own_metadata = |items: &[SourcedItem]| {
+            items
+                .iter()
+                .enumerate()
+                .filter(|&(index, _)| describes_observation(items, index))
+                .fold(entry_metadata.clone(), |m, (_, (_, item))| {
+                    m.with(item.content.clone())
+                })
+        };
+
+        match split_observations(sourced_items.clone()).as_slice() {
+            [items] => new_note(with_own_metadata(items), data_quality, items),
+            observations => {
+                let metadata = observations
+                    .iter()
+                    .map(|o| with_own_metadata(o))
+                    .collect_vec();
+                // e.g. "20/60 PH 20/30" => the other observations are without pinhole
+                let pinhole_in_text = metadata.iter().any(|m| m.pinhole == PinHole::With);
+                let mut notes = vec![];
+                let mut without_pinhole = vec![];
+                for (items, mut metadata) in observations.iter().zip(metadata) {
+                    if pinhole_in_text && metadata.pinhole == PinHole::Unknown {
+                        metadata.pinhole = PinHole::Without;
+                    }
+                    let mut items = items.to_vec();
+                    if metadata.pinhole != PinHole::With {
+                        without_pinhole = observation_details(&items);
+                    } else if items.iter().any(|(_, i)| is_no_improvement(&i.content)) {
+                        // e.g. "20/60 PH NI" => the pinhole acuity is also 20/60
+                        items.splice(0..0, without_pinhole.clone());
+                    }
+                    let data_quality = items.iter().map(|(_, i)| &i.data_quality).max();
+                    let data_quality = data_quality.cloned().unwrap_or_default();
+                    notes.push(new_note(metadata, data_quality, &items)?);
+                }
+                // The values belong to the observations, so the field as a whole has none of its own
+                let note = new_note(entry_metadata.clone(), NoValue, &[])?;
+                let parsed_items = sourced_items
+                    .iter()
+                    .map(|(source, item)| SpannedItem::new(*source, item))
+                    .collect();
+                Ok(Self {
+                    data_quality: Observations,
+                    parsed_items,
+                    observations: notes,
+                    ..note
+                })
+            }
+        }
+    }
+
+    /// Like `new`, for items that have already been found in `text` and `text_plus`
+    fn from_items(
+        entry_metadata: EntryMetadata,
+        (text, text_plus): (&str, &str),
+        mut data_quality: DataQuality,
+        parsed_items: Vec<SpannedItem>,
+        charts: &Charts,
+        range_convention: RangeConvention,
     ) -> VisualAcuityResult<Self> {
-        let mut data_quality = parsed_text.data_quality.max(parsed_text_plus.data_quality);
-        let Content {
-            input: text,
-            content: parsed_text,
-            ..
-        } = parsed_text;
-        let Content {
-            input: text_plus,
-            content: parsed_text_plus,
-            ..
-        } = parsed_text_plus;
-        let parsed_notes = ParsedItemCollection(
-            [parsed_text, parsed_text_plus]
-                .into_iter()
-                .flatten()
-                .collect(),
-        );
-        let sifted = &SiftedParsedItems::sift(parsed_notes);
+        let parsed_notes = parsed_items.iter().map(|s| s.item.clone()).collect();
+        let sifted = &SiftedParsedItems::sift(parsed_notes, charts);
         let base_acuity = &sifted.base_acuity;
-        let log_mar_base = base_acuity.clone().then(|v| v.log_mar_base());
+        // e.g. "20/40-20/50" => convert just one of the lines (or the midpoint)
+        let converted_acuity = base_acuity
+            .clone()
+            .then(|v| range_convention.resolve(v, charts));
+        let log_mar_base = base_acuity
+            .clone()
+            .then(|v| range_convention.log_mar_plus_letters(v, charts, &Vec::new()));
         let log_mar_base_plus_letters = base_acuity
             .clone()
-            .then(|v| v.log_mar_plus_letters(&sifted.plus_letters));
+            .then(|v| range_convention.log_mar_plus_letters(v, charts, &sifted.plus_letters));
+        let decimal_acuity = log_mar_base_plus_letters
+            .clone()
+            .map(|v| v.decimal_acuity());
+        let minimum_angle_of_resolution = log_mar_base_plus_letters
+            .clone()
+            .map(|v| v.minimum_angle_of_resolution());
+        let visual_acuity_score = log_mar_base_plus_letters
+            .clone()
+            .map(|v| v.visual_acuity_score());
         let other_options = &sifted
             .acuities
             .iter()
@@ -132,18 +280,59 @@ impl VisitNote {
             .collect_vec();
         let va_format = get_va_format(&base_acuity, other_options);
         let extracted_value = extract_value(&base_acuity, &sifted.other_observations);
-        let snellen_equivalent = base_acuity.clone().then(|v| v.snellen_equivalent());
+        let snellen_equivalent = converted_acuity
+            .clone()
+            .then(|v| v.with_charts(charts).snellen_equivalent());
         let plus_letters = sifted.plus_letters.clone();
+        let low_vision_convention = match base_acuity {
+            OptionResult::Some(item @ NearTotalLoss(..)) => item
+                .find_chart_row(charts)
+                .ok()
+                .and_then(|row| LowVisionConvention::from_chart_name(&row.chart_name)),
+            _ => None,
+        };
+
+        let optotype = match base_acuity {
+            OptionResult::Some(OptotypeItem(optotype, ..)) => Some(*optotype),
+            _ => None,
+        };
+        // e.g. "20/40 CSM" => the fixation goes along with the acuity
+        let fixation = sifted.fixations.last().cloned();
+        let not_taken_reason = match base_acuity {
+            OptionResult::None => sifted
+                .other_observations
+                .iter()
+                .find_map(|item| match item {
+                    NotTakenItem(reason) => Some(reason.clone()),
+                    _ => None,
+                }),
+            _ => None,
+        };
 
         data_quality = match base_acuity {
             OptionResult::None => NoValue,
             OptionResult::Err(MultipleValues(_)) => Multiple,
+            OptionResult::Some(AcuityRange(..)) => Range,
             _ => data_quality,
         };
+        let qualifier = match sifted.qualifiers.iter().unique().at_most_one() {
+            Ok(qualifier) => qualifier.copied().unwrap_or_default(),
+            Err(_) => {
+                // e.g. "> 20/40 < 20/20" => neither bound on its own
+                if data_quality == Exact {
+                    data_quality = ConvertibleFuzzy;
+                }
+                Qualifier::Exact
+            }
+        };
         if plus_letters.len() > 1 && data_quality == Exact {
             // "20/40 +3 -2" => not exact. Is there a cleaner way to do this?
             data_quality = ConvertibleFuzzy;
         }
+        if sifted.partial_lines_disagree && data_quality == Exact {
+            // e.g. "20/25 4/5", where the 20/25 row has 6 letters => not exact either
+            data_quality = ConvertibleFuzzy;
+        }
 
         let EntryMetadata {
             correction,
@@ -166,10 +355,122 @@ impl VisitNote {
             snellen_equivalent,
             log_mar_base,
             log_mar_base_plus_letters,
+            decimal_acuity,
+            minimum_angle_of_resolution,
+            visual_acuity_score,
+            low_vision_convention,
+            optotype,
+            qualifier,
+            fixation,
+            not_taken_reason,
+            parsed_items,
+            observations: vec![],
+            inherited_from: None,
         })
     }
 }
 
+/// A parsed item, along with which of the input fields it was found in
+type SourcedItem<'a> = (TextSource, Content<'a, ParsedItem>);
+
+/// Split items into separate observations, each with its own metadata either before the acuity
+/// (e.g. "OD 20/40 OS 20/30") or after it (e.g. "20/40 OD, 20/30 OS"). Metadata without an acuity
+/// of its own (e.g. "OD 20/40 cc") stays with its neighbor.
+fn split_observations(items: Vec<SourcedItem>) -> Vec<Vec<SourcedItem>> {
+    let has_acuity = |items: &[SourcedItem]| items.iter().any(|(_, i)| is_observation(&i.content));
+
+    let leading = split_before_metadata(items.clone());
+    let observations = match leading.last() {
+        Some(last) if leading.len() > 1 && !has_acuity(last) => {
+            // Metadata after each acuity is metadata before each acuity, in reverse
+            let reversed = split_before_metadata(items.into_iter().rev().collect());
+            let reversed = reversed.into_iter().rev();
+            reversed.map(|o| o.into_iter().rev().collect()).collect()
+        }
+        _ => leading,
+    };
+
+    let mut result: Vec<Vec<SourcedItem>> = vec![];
+    let mut pending = vec![];
+    for mut observation in observations {
+        pending.append(&mut observation);
+        if has_acuity(&pending) {
+            result.push(std::mem::take(&mut pending));
+        }
+    }
+    match result.last_mut() {
+        Some(last) => last.append(&mut pending),
+        None => result.push(pending),
+    }
+    result
+}
+
+/// Start a new observation at each metadata item that follows an acuity
+fn split_before_metadata(items: Vec<SourcedItem>) -> Vec<Vec<SourcedItem>> {
+    let mut observations: Vec<Vec<SourcedItem>> = vec![vec![]];
+    for item in items {
+        let current = observations.last_mut().expect("never empty");
+        let has_acuity = current.iter().any(|(_, i)| is_observation(&i.content));
+        if is_metadata(&item.1.content) && has_acuity {
+            observations.push(vec![item]);
+        } else {
+            current.push(item);
+        }
+    }
+    observations
+}
+
+/// Whether the item at `index` is next to an observation it can describe, with nothing but other
+/// metadata, details or punctuation in between (e.g. "OD 20/40 cc", but not "no time for PH")
+fn describes_observation(items: &[SourcedItem], index: usize) -> bool {
+    fn is_between(item: &ParsedItem) -> bool {
+        match item {
+            Text(s) => !s.chars().any(char::is_alphanumeric),
+            _ => !is_observation(item) && !matches!(item, Unhandled(_)),
+        }
+    }
+    fn nearest_is_observation<'b, 'a: 'b>(
+        mut neighbors: impl Iterator<Item = &'b SourcedItem<'a>>,
+    ) -> bool {
+        neighbors
+            .find(|(_, i)| !is_between(&i.content))
+            .is_some_and(|(_, i)| is_observation(&i.content))
+    }
+    nearest_is_observation(items[..index].iter().rev())
+        || nearest_is_observation(items[index + 1..].iter())
+}
+
+/// The items describing an observation (e.g. "20/60 -1"), without its metadata or ignored text
+fn observation_details<'a>(items: &[SourcedItem<'a>]) -> Vec<SourcedItem<'a>> {
+    let is_detail = |item: &ParsedItem| !is_metadata(item) && !matches!(item, Text(_));
+    items
+        .iter()
+        .filter(|(_, i)| is_detail(&i.content))
+        .cloned()
+        .collect()
+}
+
+/// e.g. "NI" or "no improvement", typically after pinhole
+fn is_no_improvement(item: &ParsedItem) -> bool {
+    matches!(item, CrossReferenceItem(s) if s == "NI")
+}
+
+fn is_metadata(item: &ParsedItem) -> bool {
+    matches!(
+        item,
+        DistanceItem(_) | LateralityItem(_) | CorrectionItem(_) | PinHoleItem(_)
+    )
+}
+
+/// Whether an item is the observation itself, e.g. an acuity, rather than a detail about it
+fn is_observation(item: &ParsedItem) -> bool {
+    !is_metadata(item)
+        && !matches!(
+            item,
+            PlusLettersItem(_) | PartialLineItem(_) | QualifierItem(_) | Text(_) | Unhandled(_)
+        )
+}
+
 /// Retrieve the "normalized" text representing the primary observation in a given EHR note.
 fn extract_value(item: &OptionResult<ParsedItem>, other_observations: &Vec<ParsedItem>) -> String {
     match item {
@@ -212,7 +513,11 @@ fn get_va_format(
 struct SiftedParsedItems {
     acuities: Vec<ParsedItem>,
     other_observations: Vec<ParsedItem>,
+    fixations: Vec<Fixation>,
     plus_letters: Vec<i32>,
+    partial_lines: Vec<PartialLine>,
+    partial_lines_disagree: bool,
+    qualifiers: Vec<Qualifier>,
     lateralities: Vec<Laterality>,
     distances: Vec<DistanceOfMeasurement>,
     corrections: Vec<Correction>,
@@ -223,7 +528,7 @@ struct SiftedParsedItems {
 
 impl SiftedParsedItems {
     /// Iterates through parsed items, assigning each variant of `ParsedItem` into a bin/category.
-    fn sift(parsed_notes: ParsedItemCollection) -> Self {
+    fn sift(parsed_notes: ParsedItemCollection, charts: &Charts) -> Self {
         let mut result = Self::default();
         for item in parsed_notes {
             match item {
@@ -231,13 +536,22 @@ impl SiftedParsedItems {
                 | Jaeger { .. }
                 | Teller { .. }
                 | ETDRS { .. }
+                | Decimal { .. }
+                | LogMar(_)
                 | VisualResponse { .. }
                 | CrossReferenceItem(_)
-                | NearTotalLoss { .. } => result.acuities.push(item),
+                | NearTotalLoss { .. }
+                | NearCard { .. }
+                | OptotypeItem { .. }
+                | Keeler { .. }
+                | AcuityRange(..) => result.acuities.push(item),
 
                 NotTakenItem(_) => result.other_observations.push(item),
+                FixationItem(value) => result.fixations.push(value),
 
                 PlusLettersItem(value) => result.plus_letters.push(value),
+                PartialLineItem(value) => result.partial_lines.push(value),
+                QualifierItem(value) => result.qualifiers.push(value),
                 DistanceItem(value) => result.distances.push(value),
                 LateralityItem(value) => result.lateralities.push(value),
                 CorrectionItem(value) => result.corrections.push(value),
@@ -247,18 +561,32 @@ impl SiftedParsedItems {
                 Unhandled(_) => result.unhandled.push(item),
             }
         }
-        result.base_acuity = Self::base_acuity_(&result);
+        result.base_acuity = Self::base_acuity_(&result, charts);
+
+        // e.g. "20/40 3/5" => "20/40 -2", as written, even if the chart row has more letters
+        let n_letters = match &result.base_acuity {
+            OptionResult::Some(item) => item.with_charts(charts).n_letters(),
+            _ => None,
+        };
+        let partial_lines = result.partial_lines.iter();
+        result.partial_lines_disagree = partial_lines.clone().any(|p| !p.agrees_with(n_letters));
+        let plus_letters = partial_lines.map(PartialLine::plus_letters);
+        result.plus_letters.extend(plus_letters);
         result
     }
 
     /// Given `ParsedItem`s, determine which one reperesents a "base acuity." If none are present,
     /// consider "other observations" (e.g. binocular fixation) that might be the primary observation.
-    fn base_acuity_(&self) -> OptionResult<ParsedItem> {
+    fn base_acuity_(&self, charts: &Charts) -> OptionResult<ParsedItem> {
+        // e.g. "20/60 NI" => "no improvement" on 20/60, or "20/40 (ditto)", where 20/40 is the acuity
+        let is_cross_reference = |item: &ParsedItem| matches!(item, CrossReferenceItem(_));
+        let has_acuity = self.acuities.iter().any(|a| !is_cross_reference(a));
         let unique_acuities = self
             .acuities
             .iter()
+            .filter(|&acuity| !(has_acuity && is_cross_reference(acuity)))
             .rev() // Take the *last* equivalent thing (e.g. ETDRS)
-            .unique_by(|&acuity| acuity.snellen_equivalent())
+            .unique_by(|&acuity| acuity.with_charts(charts).snellen_equivalent())
             .collect_vec();
 
         let acuity_item = unique_acuities.into_iter().rev().at_most_one();
@@ -268,7 +596,11 @@ impl SiftedParsedItems {
             Ok(Some(VisualResponse(v))) => OptionResult::Some(VisualResponse(v.clone())),
             Ok(Some(v)) => OptionResult::Some(v.clone()),
             Err(e) => OptionResult::Err(e.into()),
-            Ok(None) => OptionResult::None,
+            // e.g. "CSM" on its own => the fixation is the primary observation
+            Ok(None) => match self.fixations.last() {
+                Some(fixation) => OptionResult::Some(FixationItem(fixation.clone())),
+                None => OptionResult::None,
+            },
         }
     }
 }
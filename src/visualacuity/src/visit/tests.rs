@@ -4,10 +4,12 @@ mod tests {
     use std::fmt::Debug;
     use test_case::test_case;
 
+    use crate::helpers::RoundPlaces;
     use crate::visit::Correction::*;
     use crate::visit::DistanceOfMeasurement::*;
     use crate::visit::Laterality::*;
     use crate::visit::*;
+    use crate::VisualAcuityError::*;
     use crate::*;
 
     type R<T> = VisualAcuityResult<T>;
@@ -16,13 +18,22 @@ mod tests {
     fn test_base_and_plus_letters() -> R<()> {
         let notes = "CSM";
 
-        let parsed_notes = Parser::new().parse_text(notes).content;
-        let sifted = &SiftedParsedItems::sift(parsed_notes);
+        let parsed_notes = Parser::new()
+            .parse_text(notes, &Charts::default())
+            .content
+            .into_iter()
+            .map(|item| item.content)
+            .collect();
+        let charts = &Charts::default();
+        let sifted = &SiftedParsedItems::sift(parsed_notes, charts);
 
-        assert_eq!(vec![VisualResponse(s!("CSM"))], sifted.acuities);
         assert_eq!(
-            OptionResult::Some(VisualResponse(s!("CSM"))),
-            sifted.base_acuity_()
+            vec![Fixation::new(FixationPreference::CSM, None)],
+            sifted.fixations
+        );
+        assert_eq!(
+            OptionResult::Some(FixationItem(Fixation::new(FixationPreference::CSM, None))),
+            sifted.base_acuity_(charts)
         );
         Ok(())
     }
@@ -47,6 +58,28 @@ mod tests {
             snellen_equivalent: OptionResult::Some((20, 20).into()),
             log_mar_base: OptionResult::Some(0.0),
             log_mar_base_plus_letters: OptionResult::Some(0.0323),
+            decimal_acuity: OptionResult::Some(0.9283),
+            minimum_angle_of_resolution: OptionResult::Some(1.0772),
+            visual_acuity_score: OptionResult::Some(98.3848),
+            low_vision_convention: None,
+            optotype: None,
+            qualifier: Qualifier::Exact,
+            fixation: None,
+            not_taken_reason: None,
+            parsed_items: vec![
+                SpannedItem {
+                    source: TextSource::Text,
+                    span: 0..5,
+                    item: SnellenFraction(s!("20/20")),
+                },
+                SpannedItem {
+                    source: TextSource::TextPlus,
+                    span: 0..2,
+                    item: PlusLettersItem(-2),
+                },
+            ],
+            observations: vec![],
+            inherited_from: None,
         })),
         ])
     )]
@@ -84,6 +117,333 @@ mod tests {
         assert_almost_eq!(actual, expected, 4, "{visit_notes:?}");
     }
 
+    #[test_case(
+        [("Visual Acuity", "  20/40 -1 pt was squinting"), ("Visual Acuity +/-", "+2")],
+        Ok(vec![
+            (TextSource::Text, s!("20/40"), SnellenFraction(s!("20/40"))),
+            (TextSource::Text, s!("-1"), PlusLettersItem(-1)),
+            (TextSource::Text, s!("pt was squinting"), Text(s!("pt was squinting"))),
+            (TextSource::TextPlus, s!("+2"), PlusLettersItem(2)),
+        ])
+    )]
+    fn test_visit_parsed_items<X>(
+        visit_notes: X,
+        expected: R<Vec<(TextSource, String, ParsedItem)>>,
+    ) where
+        X: Into<VisitInput>,
+    {
+        test_visit_values(visit_notes, expected, |v: VisitNote| {
+            v.parsed_items
+                .iter()
+                .map(|SpannedItem { source, span, item }| {
+                    let text = match source {
+                        TextSource::Text => &v.text,
+                        TextSource::TextPlus => &v.text_plus,
+                    };
+                    (*source, text[span.clone()].to_string(), item.clone())
+                })
+                .collect()
+        });
+    }
+
+    #[test_case([("Visual Acuity", "20/20")], Ok((Some(1.0), Some(1.0), Some(100.0))))]
+    #[test_case([("Visual Acuity", "20/40")], Ok((Some(0.5), Some(2.0), Some(84.95))))]
+    #[test_case([("Visual Acuity", "20/200")], Ok((Some(0.1), Some(10.0), Some(50.0))))]
+    #[test_case([("Visual Acuity", "CSM")], Ok((None, None, None)))]
+    fn test_visit_acuity_scales<X>(
+        visit_notes: X,
+        expected: R<(Option<f64>, Option<f64>, Option<f64>)>,
+    ) where
+        X: Into<VisitInput>,
+    {
+        test_visit_values(visit_notes, expected, |v: VisitNote| {
+            let scale = |value: OptionResult<f64>| match value.round_places(2) {
+                OptionResult::Some(value) => Some(value),
+                _ => None,
+            };
+            (
+                scale(v.decimal_acuity),
+                scale(v.minimum_angle_of_resolution),
+                scale(v.visual_acuity_score),
+            )
+        });
+    }
+
+    #[test_case([("Visual Acuity", "HOTV 20/32")], Ok(Some(Optotype::HOTV)))]
+    #[test_case([("Visual Acuity", "Allen 20/30 @ 10ft")], Ok(Some(Optotype::Allen)))]
+    #[test_case([("Visual Acuity", "20/32")], Ok(None))]
+    fn test_visit_optotype<X>(visit_notes: X, expected: R<Option<Optotype>>)
+    where
+        X: Into<VisitInput>,
+    {
+        test_visit_values(visit_notes, expected, |v: VisitNote| v.optotype);
+    }
+
+    #[test_case([("Visual Acuity", "20/40")], Ok(Qualifier::Exact))]
+    #[test_case([("Visual Acuity", "<20/400")], Ok(Qualifier::LessThan))]
+    #[test_case([("Visual Acuity", "worse than 20/200")], Ok(Qualifier::LessThan))]
+    #[test_case([("Visual Acuity", "> 20/15")], Ok(Qualifier::GreaterThan))]
+    #[test_case([("Visual Acuity", "at least 20/40")], Ok(Qualifier::GreaterThan))]
+    #[test_case([("Visual Acuity", "20/400+")], Ok(Qualifier::GreaterThan))]
+    #[test_case([("Visual Acuity", "20/40 +2")], Ok(Qualifier::Exact); "plus letters")]
+    fn test_visit_qualifier<X>(visit_notes: X, expected: R<Qualifier>)
+    where
+        X: Into<VisitInput>,
+    {
+        test_visit_values(visit_notes, expected, |v: VisitNote| v.qualifier);
+    }
+
+    #[test_case([("Visual Acuity", "OD 20/40 OS 20/30")], Ok(vec![
+        (OD, Correction::Unknown, PinHole::Unknown, s!("20/40")),
+        (OS, Correction::Unknown, PinHole::Unknown, s!("20/30")),
+    ]))]
+    #[test_case([("Visual Acuity", "20/40 -1 OD, 20/30 OS")], Ok(vec![
+        (OD, Correction::Unknown, PinHole::Unknown, s!("20/40")),
+        (OS, Correction::Unknown, PinHole::Unknown, s!("20/30")),
+    ]))]
+    #[test_case([("Right Eye", "cc 20/25, sc 20/80")], Ok(vec![
+        (OD, CC, PinHole::Unknown, s!("20/25")),
+        (OD, SC, PinHole::Unknown, s!("20/80")),
+    ]))]
+    #[test_case([("Left Eye SC", "20/40 PH 20/25")], Ok(vec![
+        (OS, SC, PinHole::Without, s!("20/40")),
+        (OS, SC, PinHole::With, s!("20/25")),
+    ]))]
+    #[test_case([("Right Eye", "20/60 -1 PH NI")], Ok(vec![
+        (OD, Correction::Unknown, PinHole::Without, s!("20/60")),
+        (OD, Correction::Unknown, PinHole::With, s!("20/60")),
+    ]))]
+    #[test_case([("Right Eye", "20/60 ph no improvement")], Ok(vec![
+        (OD, Correction::Unknown, PinHole::Without, s!("20/60")),
+        (OD, Correction::Unknown, PinHole::With, s!("20/60")),
+    ]))]
+    #[test_case([("Visual Acuity", "OD 20/40 cc")], Ok(vec![]); "one observation")]
+    fn test_visit_observations<X>(
+        visit_notes: X,
+        expected: R<Vec<(Laterality, Correction, PinHole, String)>>,
+    ) where
+        X: Into<VisitInput>,
+    {
+        test_visit_values(visit_notes, expected, |v: VisitNote| {
+            v.observations
+                .into_iter()
+                .map(|o| (o.laterality, o.correction, o.pinhole, o.extracted_value))
+                .collect()
+        });
+    }
+
+    #[test_case([("Visual Acuity", "OD 20/40 OS 20/30")], Ok((Observations, s!(""), OptionResult::None, 2)))]
+    #[test_case([("Visual Acuity", "20/40 OD 20/30")], Ok((Observations, s!(""), OptionResult::None, 2)))]
+    fn test_visit_observations_field<X>(
+        visit_notes: X,
+        expected: R<(DataQuality, String, OptionResult<f64>, usize)>,
+    ) where
+        X: Into<VisitInput>,
+    {
+        test_visit_values(visit_notes, expected, |v: VisitNote| {
+            let n_observations = v.observations.len();
+            let log_mar = v.log_mar_base_plus_letters;
+            (v.data_quality, v.extracted_value, log_mar, n_observations)
+        });
+    }
+
+    #[test_case([("Right Eye", "20/60 -1 PH NI")], Ok((PinHole::With, OptionResult::Some(0.49), DataQuality::Exact)))]
+    #[test_case([("Right Eye", "PH NI")], Ok((PinHole::With, OptionResult::Err(NoSnellenEquivalent(s!("NI"))), CrossReference)))]
+    #[test_case([("Right Eye PH", "20/60 NI")], Ok((PinHole::With, OptionResult::Some(0.48), DataQuality::Exact)))]
+    fn test_visit_pinhole_no_improvement<X>(
+        visit_notes: X,
+        expected: R<(PinHole, OptionResult<f64>, DataQuality)>,
+    ) where
+        X: Into<VisitInput>,
+    {
+        test_visit_values(visit_notes, expected, |v: VisitNote| {
+            let v = v.observations.last().cloned().unwrap_or(v);
+            let log_mar = v.log_mar_base_plus_letters.round_places(2);
+            (v.pinhole, log_mar, v.data_quality)
+        });
+    }
+
+    #[test_case([("Right Eye", "20/40 PH")], Ok(PinHole::With))]
+    #[test_case([("Right Eye", "20/400 no time for PH")], Ok(PinHole::Unknown); "not next to the acuity")]
+    #[test_case([("Right Eye", "20/40, did not try PH")], Ok(PinHole::Unknown); "after other text")]
+    fn test_visit_pinhole_in_text<X>(visit_notes: X, expected: R<PinHole>)
+    where
+        X: Into<VisitInput>,
+    {
+        test_visit_values(visit_notes, expected, |v: VisitNote| v.pinhole);
+    }
+
+    #[test_case([("Visual Acuity", "OD 20/40 cc")], Ok((OD, CC, DataQuality::Exact)))]
+    #[test_case([("Left Eye", "20/30 near")], Ok((OS, Correction::Unknown, DataQuality::Exact)))]
+    #[test_case([("Visual Acuity", "OD 20/40 OS 20/30")], Ok((Laterality::Unknown, Correction::Unknown, Observations)))]
+    fn test_visit_metadata_in_text<X>(
+        visit_notes: X,
+        expected: R<(Laterality, Correction, DataQuality)>,
+    ) where
+        X: Into<VisitInput>,
+    {
+        test_visit_values(visit_notes, expected, |v: VisitNote| {
+            (v.laterality, v.correction, v.data_quality)
+        });
+    }
+
+    #[test_case(
+        [("Right Eye", "20/60 -1"), ("Right Eye PH", "NI"), ("Left Eye", "20/30")],
+        Ok(BTreeMap::from([
+            (s!("Left Eye"), (s!("20/30"), None)),
+            (s!("Right Eye"), (s!("20/60"), None)),
+            (s!("Right Eye PH"), (s!("20/60"), Some(s!("Right Eye")))),
+        ]))
+    )]
+    #[test_case(
+        [("Right Eye Distance CC", "20/40"), ("Left Eye Distance CC", "same")],
+        Ok(BTreeMap::from([
+            (s!("Left Eye Distance CC"), (s!("20/40"), Some(s!("Right Eye Distance CC")))),
+            (s!("Right Eye Distance CC"), (s!("20/40"), None)),
+        ]))
+    )]
+    #[test_case(
+        [("Right Eye", "20/40"), ("Both Eyes", "20/30"), ("Left Eye", "ditto")],
+        Ok(BTreeMap::from([
+            (s!("Both Eyes"), (s!("20/30"), None)),
+            (s!("Left Eye"), (s!("ditto"), None)),
+            (s!("Right Eye"), (s!("20/40"), None)),
+        ]));
+        "ambiguous"
+    )]
+    #[test_case(
+        [("Right Eye Distance CC", "20/30"), ("Left Eye Distance CC", "same as last visit")],
+        Ok(BTreeMap::from([
+            (s!("Left Eye Distance CC"), (s!(""), None)),
+            (s!("Right Eye Distance CC"), (s!("20/30"), None)),
+        ]));
+        "an earlier visit"
+    )]
+    fn test_visit_cross_references<X>(
+        visit_notes: X,
+        expected: R<BTreeMap<String, (String, Option<String>)>>,
+    ) where
+        X: Into<VisitInput>,
+    {
+        let actual = Parser::new().parse_visit(visit_notes.into()).map(|visit| {
+            visit
+                .into_iter()
+                .map(|(key, note)| {
+                    let note = note.expect("TEST");
+                    (key, (note.extracted_value, note.inherited_from))
+                })
+                .collect()
+        });
+        assert_eq!(actual, expected);
+    }
+
+    #[test_case("20/40 same as last visit")]
+    #[test_case("20/40, stable/same")]
+    #[test_case("same as before 20/40")]
+    #[test_case("20/40 (ditto)")]
+    #[test_case("20/40 see above")]
+    fn test_visit_cross_reference_with_acuity(note: &str) -> R<()> {
+        // The field's own acuity wins over a reference to another field
+        let visit = Parser::new().parse_visit(
+            [
+                ("Right Eye Distance CC", "20/30"),
+                ("Left Eye Distance CC", note),
+            ]
+            .into(),
+        )?;
+        let note = visit
+            .0
+            .get("Left Eye Distance CC")
+            .cloned()
+            .flatten()
+            .expect("TEST");
+        assert_eq!(note.extracted_value, "20/40");
+        assert_eq!(note.inherited_from, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_visit_cross_reference_values() -> R<()> {
+        let visit = Parser::new()
+            .parse_visit([("Right Eye", "20/60 -1"), ("Right Eye PH", "NI")].into())?;
+        let note = visit
+            .0
+            .get("Right Eye PH")
+            .cloned()
+            .flatten()
+            .expect("TEST");
+        assert_eq!(note.data_quality, DataQuality::CrossReference);
+        assert_eq!(note.va_format, Ok(VAFormat::Snellen));
+        assert_eq!(note.pinhole, PinHole::With);
+        assert_eq!(note.plus_letters, vec![-1]);
+        assert_eq!(
+            note.log_mar_base_plus_letters.round_places(2),
+            OptionResult::Some(0.49)
+        );
+        Ok(())
+    }
+
+    /// Preference, (central, steady, maintained), and eye preference
+    type FixationFields = (
+        FixationPreference,
+        (Option<bool>, Option<bool>, Option<bool>),
+        Option<EyePreference>,
+    );
+
+    #[test_case([("Left Eye", "CSM")], Ok(Some((FixationPreference::CSM, (Some(true), Some(true), Some(true)), None))))]
+    #[test_case([("Left Eye", "UCUSM")], Ok(Some((FixationPreference::UCUSM, (Some(false), Some(false), Some(true)), None))))]
+    #[test_case([("Left Eye", "CSUM pref")], Ok(Some((FixationPreference::CSUM, (Some(true), Some(true), Some(false)), Some(EyePreference::Prefers)))))]
+    #[test_case([("Left Eye", "cusum holds")], Ok(Some((FixationPreference::CUSUM, (Some(true), Some(false), Some(false)), Some(EyePreference::Holds)))))]
+    #[test_case([("Left Eye", "F+F")], Ok(Some((FixationPreference::FixAndFollow, (None, None, None), None))))]
+    #[test_case([("Left Eye", "fix no follow")], Ok(Some((FixationPreference::FixNoFollow, (None, None, None), None))))]
+    #[test_case([("Left Eye", "eccentric fixation")], Ok(Some((FixationPreference::Eccentric, (None, None, None), None))))]
+    #[test_case([("Left Eye", "BTL")], Ok(None))]
+    #[test_case([("Left Eye", "20/20")], Ok(None))]
+    fn test_visit_fixation<X>(visit_notes: X, expected: R<Option<FixationFields>>)
+    where
+        X: Into<VisitInput>,
+    {
+        test_visit_values(visit_notes, expected, |v: VisitNote| {
+            v.fixation.map(|f| {
+                (
+                    f.preference,
+                    (f.central, f.steady, f.maintained),
+                    f.eye_preference,
+                )
+            })
+        });
+    }
+
+    #[test_case([("Left Eye", "20/40 CSM")], Ok((s!("20/40"), DataQuality::Exact, Some(FixationPreference::CSM))))]
+    #[test_case([("Left Eye", "CSM prefers")], Ok((s!("CSM prefers"), DataQuality::Exact, Some(FixationPreference::CSM))))]
+    #[test_case([("Left Eye", "20/400 ecc fix")], Ok((s!("20/400"), DataQuality::Exact, Some(FixationPreference::Eccentric))))]
+    fn test_visit_fixation_with_acuity<X>(
+        visit_notes: X,
+        expected: R<(String, DataQuality, Option<FixationPreference>)>,
+    ) where
+        X: Into<VisitInput>,
+    {
+        test_visit_values(visit_notes, expected, |v: VisitNote| {
+            let preference = v.fixation.map(|f| f.preference);
+            (v.extracted_value, v.data_quality, preference)
+        });
+    }
+
+    #[test_case([("Left Eye", "NT")], Ok(Some(NotTakenReason::NT)))]
+    #[test_case([("Left Eye", "refused")], Ok(Some(NotTakenReason::Refused)))]
+    #[test_case([("Left Eye", "poor cooperation")], Ok(Some(NotTakenReason::PoorCooperation)))]
+    #[test_case([("Left Eye", "patched")], Ok(Some(NotTakenReason::Patched)))]
+    #[test_case([("Left Eye", "dilated")], Ok(Some(NotTakenReason::Dilated)))]
+    #[test_case([("Left Eye", "no time")], Ok(Some(NotTakenReason::NoTime)))]
+    #[test_case([("Left Eye", "20/20")], Ok(None))]
+    #[test_case([("Left Eye", "asdf")], Ok(None); "not parsed")]
+    fn test_visit_not_taken_reason<X>(visit_notes: X, expected: R<Option<NotTakenReason>>)
+    where
+        X: Into<VisitInput>,
+    {
+        test_visit_values(visit_notes, expected, |v: VisitNote| v.not_taken_reason);
+    }
+
     #[test_case([("Visual Acuity", "20/20")], Ok(Correction::Unknown))]
     #[test_case([("Left Eye CC", "20/20")], Ok(CC))]
     #[test_case([("Left Eye SC", "20/20")], Ok(SC))]
@@ -125,7 +485,7 @@ mod tests {
         test_visit_values(visit_notes, expected, |v: VisitNote| v.laterality);
     }
 
-    #[test_case([("Visual Acuity", "20/30 OS")], Ok(ConvertibleFuzzy))]
+    #[test_case([("Visual Acuity", "20/30 OS")], Ok(Exact))]
     #[test_case([("Visual Acuity", "CSM")], Ok(Exact))]
     #[test_case([("Visual Acuity", "CSM pref")], Ok(Exact))]
     fn test_visit_data_quality<'a, X>(visit_notes: X, expected: R<DataQuality>)
@@ -135,6 +495,46 @@ mod tests {
         test_visit_values(visit_notes, expected, |v: VisitNote| v.data_quality);
     }
 
+    #[test]
+    fn test_visit_partial() -> R<()> {
+        let visit_notes: VisitInput = [
+            ("Left Eye Distance SC", "20/30"),
+            ("Right Eye Distance SC", "20/40"),
+        ]
+        .into();
+        let parser = Parser::new();
+        let PartialVisit(mut partial) = parser.parse_visit_partial(visit_notes.clone());
+        let Visit(expected) = parser.parse_visit(visit_notes)?;
+        assert_eq!(
+            partial.clone(),
+            expected
+                .into_iter()
+                .map(|(key, note)| (key, Ok(note)))
+                .collect()
+        );
+
+        // One failed field doesn't spoil the rest, unless we need the whole visit
+        partial.insert(s!("Comments"), Err(ParseError(s!("Comments"))));
+        let partial = PartialVisit(partial);
+        let succeeded = partial
+            .clone()
+            .into_iter()
+            .filter_map(|(key, note)| Some((key, note.ok()??.extracted_value)))
+            .collect_vec();
+        assert_eq!(
+            succeeded,
+            vec![
+                (s!("Left Eye Distance SC"), s!("20/30")),
+                (s!("Right Eye Distance SC"), s!("20/40")),
+            ]
+        );
+        assert_eq!(
+            Visit::try_from(partial),
+            Err(MultipleErrors(vec![ParseError(s!("Comments"))]))
+        );
+        Ok(())
+    }
+
     fn test_visit_values<V, T, F>(visit_notes: V, expected: R<T>, f: F)
     where
         V: Into<VisitInput>,